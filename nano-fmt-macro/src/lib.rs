@@ -1,14 +1,15 @@
-//! This crate provides `write!` and `P!` macros for use with `nano-fmt` crate.
+//! This crate provides `write!`, `writeln!`, their fallible `try_` versions, and `P!` macros
+//! for use with `nano-fmt` crate.
 //!
 //! This code is based on code from `ufmt-macros` crate by Jorge Aparicio
 //! <jorge@japaric.io>. It is licensed under MIT or Apache-2.0 licenses.
-use std::{borrow::Cow, cmp::Ordering, mem};
+use std::{borrow::Cow, mem};
 
 use proc_macro::TokenStream;
-use proc_macro2::Span;
-use quote::quote;
+use proc_macro2::{Ident, Span};
+use quote::{format_ident, quote};
 use syn::{
-    Expr, LitByteStr, LitStr, Token,
+    Expr, Lifetime, LitByteStr, LitStr, Token,
     parse::{self, Parse, ParseStream},
     parse_macro_input,
     punctuated::Punctuated,
@@ -17,17 +18,28 @@ use syn::{
 
 struct Input {
     formatter: Expr,
-    _comma: Token![,],
-    literal: LitStr,
+    _comma: Option<Token![,]>,
+    literal: Option<Literal>,
     _comma2: Option<Token![,]>,
-    args: Punctuated<Expr, Token![,]>,
+    args: Punctuated<Argument, Token![,]>,
 }
 
 impl Parse for Input {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let formatter = input.parse()?;
+
+        if input.is_empty() {
+            return Ok(Input {
+                formatter,
+                _comma: None,
+                literal: None,
+                _comma2: None,
+                args: Punctuated::new(),
+            });
+        }
+
         let _comma = input.parse()?;
-        let literal = input.parse()?;
+        let literal = Some(input.parse()?);
 
         if input.is_empty() {
             Ok(Input {
@@ -49,101 +61,616 @@ impl Parse for Input {
     }
 }
 
+/// Format argument, either `expr` or `name = expr`.
+struct Argument {
+    name: Option<Ident>,
+    expr: Expr,
+}
+
+impl Parse for Argument {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = if input.peek(syn::Ident) && input.peek2(Token![=]) && !input.peek2(Token![==]) {
+            let name = input.parse()?;
+            let _eq: Token![=] = input.parse()?;
+            Some(name)
+        } else {
+            None
+        };
+
+        Ok(Argument {
+            name,
+            expr: input.parse()?,
+        })
+    }
+}
+
+/// One or more adjacent string literals that are concatenated together.
+struct Literal {
+    value: String,
+    span: Span,
+}
+
+impl Parse for Literal {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let first: LitStr = input.parse()?;
+        let mut value = first.value();
+
+        while input.peek(LitStr) {
+            let next: LitStr = input.parse()?;
+            value.push_str(&next.value());
+        }
+
+        Ok(Literal {
+            value,
+            span: first.span(),
+        })
+    }
+}
+
 #[derive(Debug, PartialEq)]
 enum Piece<'a> {
-    Display,
+    Display(ArgRef<'a>, Spec),
     Str(Cow<'a, str>),
 }
 
-impl Piece<'_> {
-    fn is_str(&self) -> bool {
-        matches!(self, Piece::Str(_))
+/// Reference to the argument of a placeholder.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum ArgRef<'a> {
+    /// `{}`, the argument following the previous implicit one.
+    Next,
+    /// `{0}`, an argument by its position.
+    Index(usize),
+    /// `{name}`, a named argument or a variable captured from the scope.
+    Name(&'a str),
+}
+
+/// Alignment requested in a format specifier.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Align {
+    Left,
+    Right,
+}
+
+/// Representation requested in a format specifier.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+enum Kind {
+    #[default]
+    Display,
+    LowerHex,
+    UpperHex,
+}
+
+/// Parsed format specifier, like `{:>#08x}`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct Spec {
+    align: Option<Align>,
+    alternate: bool,
+    zero: bool,
+    width: Option<u8>,
+    kind: Kind,
+}
+
+impl Spec {
+    fn is_padded(&self) -> bool {
+        self.width.is_some() || self.zero
     }
 }
 
+/// Create a `PStr` expression for `s`.
+///
+/// On AVR and Linux, the string is defined in assembly under a symbol derived
+/// from its content. The definition is placed into a COMDAT group, so identical
+/// strings share a single copy, even across crates. Within a single assembly
+/// module, `.ifndef` skips repeated definitions. Other targets use a static
+/// per expansion.
+///
+/// On AVR, crates using this macro have to enable `asm_experimental_arch` feature.
+///
+/// With `stats` feature, a record describing the string is added to
+/// `.progmem_stats` section, see [`stats_record`].
 fn mk_pstr(s: &str) -> proc_macro2::TokenStream {
     let mut data: Vec<u8> = s.bytes().collect();
     data.push(0);
     let size = data.len();
+
+    let symbol = format!("__pstr_{:016x}_{}", fnv1a(&data), size);
+    let bytes = data.iter().map(u8::to_string).collect::<Vec<_>>().join(",");
+
+    let stats = if cfg!(feature = "stats") {
+        let span = proc_macro::Span::call_site();
+        let record = stats_record(&span.file(), span.line(), size, &symbol, s);
+        let record = asm_string(&record);
+        format!(
+            "\n.pushsection .progmem_stats,\"\",%progbits\n\
+             .ascii \"{record}\"\n\
+             .popsection"
+        )
+    } else {
+        String::new()
+    };
+    let define = |section: &str| {
+        let asm = format!(
+            ".ifndef {symbol}\n\
+             .pushsection {section}.{symbol},\"aG\",%progbits,{symbol},comdat\n\
+             .globl {symbol}\n\
+             {symbol}:\n\
+             .byte {bytes}\n\
+             .popsection\n\
+             .endif"
+        );
+        LitStr::new(&(asm + &stats), Span::call_site())
+    };
+    let avr_asm = define(".progmem.data");
+    let host_asm = define(".rodata");
+
     let data = LitByteStr::new(&data, Span::call_site());
 
     quote!({
-        #[cfg_attr(target_arch = "avr", unsafe(link_section = ".progmem.data"))]
+        // `global_asm!` is only accepted in item position.
+        #[cfg(target_arch = "avr")]
+        mod __asm {
+            ::core::arch::global_asm!(#avr_asm);
+        }
+        #[cfg(all(not(target_arch = "avr"), target_os = "linux"))]
+        mod __asm {
+            ::core::arch::global_asm!(#host_asm);
+        }
+
+        #[cfg(any(target_arch = "avr", target_os = "linux"))]
+        unsafe extern "C" {
+            #[link_name = #symbol]
+            static __s: [u8; #size];
+        }
+        #[cfg(not(any(target_arch = "avr", target_os = "linux")))]
         static __s: [u8; #size] = *#data;
-        unsafe { progmem::PStr::new(__s.as_ptr() as *const u8) }
+
+        unsafe { progmem::PStr::new((&raw const __s).cast::<u8>()) }
+    })
+}
+
+/// Maximum number of characters of a string included in a `.progmem_stats` record.
+const STATS_TEXT_LEN: usize = 24;
+
+/// Format a `.progmem_stats` record.
+///
+/// Each record is a line with tab separated call site, size of the string
+/// in bytes including the terminator, its symbol, and the beginning of the
+/// string. Strings with the same symbol share storage. Backslashes and control
+/// characters in the string are escaped, a truncated string ends with `...`.
+fn stats_record(file: &str, line: usize, size: usize, symbol: &str, text: &str) -> String {
+    let mut record = format!("{file}:{line}\t{size}\t{symbol}\t");
+    for c in text.chars().take(STATS_TEXT_LEN) {
+        match c {
+            '\t' => record.push_str("\\t"),
+            '\n' => record.push_str("\\n"),
+            '\r' => record.push_str("\\r"),
+            '\\' => record.push_str("\\\\"),
+            c if c.is_control() => record += &format!("\\x{:02x}", u32::from(c)),
+            c => record.push(c),
+        }
+    }
+    if text.chars().nth(STATS_TEXT_LEN).is_some() {
+        record.push_str("...");
+    }
+    record.push('\n');
+    record
+}
+
+/// Escape `s` for use in an assembler string literal.
+fn asm_string(s: &str) -> String {
+    let mut escaped = String::new();
+    for b in s.bytes() {
+        match b {
+            b'"' | b'\\' => {
+                escaped.push('\\');
+                escaped.push(char::from(b));
+            }
+            b'\n' => escaped.push_str("\\n"),
+            b'\t' => escaped.push_str("\\t"),
+            b' '..=b'~' => escaped.push(char::from(b)),
+            _ => escaped += &format!("\\{b:03o}"),
+        }
+    }
+    escaped
+}
+
+/// 64-bit FNV-1a hash.
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &b| {
+        (hash ^ u64::from(b)).wrapping_mul(0x0000_0100_0000_01b3)
     })
 }
 
 #[proc_macro]
 #[allow(non_snake_case)]
 pub fn P(input: TokenStream) -> TokenStream {
-    let s = parse_macro_input!(input as LitStr);
-    mk_pstr(&s.value()).into()
+    let s = parse_macro_input!(input as Literal);
+    mk_pstr(&s.value).into()
 }
 
 #[proc_macro]
 pub fn write(input: TokenStream) -> TokenStream {
     let input = parse_macro_input!(input as Input);
 
+    if input.literal.is_none() {
+        return missing_format(&input).into();
+    }
+
+    expand(input, false, false).into()
+}
+
+/// Same as `write!`, but appends `progmem::LINE_END`.
+#[proc_macro]
+pub fn writeln(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as Input);
+    expand(input, true, false).into()
+}
+
+/// Same as `write!`, but writes to a `TryNanoWrite` and returns `Result`.
+///
+/// Writing stops at the first error.
+#[proc_macro]
+pub fn try_write(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as Input);
+
+    if input.literal.is_none() {
+        return missing_format(&input).into();
+    }
+
+    expand(input, false, true).into()
+}
+
+/// Same as `try_write!`, but appends `progmem::LINE_END`.
+#[proc_macro]
+pub fn try_writeln(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as Input);
+    expand(input, true, true).into()
+}
+
+fn missing_format(input: &Input) -> proc_macro2::TokenStream {
+    parse::Error::new(input.formatter.span(), "requires at least a format string")
+        .to_compile_error()
+}
+
+fn expand(input: Input, line_end: bool, fallible: bool) -> proc_macro2::TokenStream {
     let formatter = &input.formatter;
-    let literal = input.literal;
+    let fmt = if fallible {
+        quote!(try_fmt)
+    } else {
+        quote!(fmt)
+    };
 
-    let format = literal.value();
+    let (format, span) = match &input.literal {
+        Some(literal) => (literal.value.as_str(), literal.span),
+        None => ("", Span::call_site()),
+    };
 
-    let pieces = match parse(&format, literal.span()) {
-        Err(e) => return e.to_compile_error().into(),
+    let pieces = match parse(format, span) {
+        Err(e) => return e.to_compile_error(),
         Ok(pieces) => pieces,
     };
 
-    let required_args = pieces.iter().filter(|piece| !piece.is_str()).count();
-    let supplied_args = input.args.len();
-
-    match supplied_args.cmp(&required_args) {
-        Ordering::Less => {
-            return parse::Error::new(
-                literal.span(),
-                format!(
-                    "format string requires {} arguments but {} {} supplied",
-                    required_args,
-                    supplied_args,
-                    if supplied_args == 1 { "was" } else { "were" }
-                ),
-            )
-            .to_compile_error()
-            .into();
-        }
-        Ordering::Greater => {
-            return parse::Error::new(
-                input.args[required_args].span(),
-                "argument never used".to_string(),
-            )
-            .to_compile_error()
-            .into();
+    let args: Vec<&Argument> = input.args.iter().collect();
+    if let Err(e) = check_names(&args) {
+        return e.to_compile_error();
+    }
+
+    // Resolve placeholders first to find how many times each argument is used.
+    let mut next = 0;
+    let mut resolved = vec![];
+    for piece in &pieces {
+        if let Piece::Display(arg_ref, _) = piece {
+            match resolve(*arg_ref, &args, &mut next, span) {
+                Err(e) => return e.to_compile_error(),
+                Ok(index) => resolved.push(index),
+            }
         }
-        Ordering::Equal => {}
     }
 
-    let mut exprs = vec![];
-    let mut i = 0;
-    for piece in pieces {
-        if let Piece::Str(s) = piece {
-            let pstr = mk_pstr(&s);
-            exprs.push(quote!(nano_fmt::NanoDisplay::fmt(#pstr, #formatter);));
+    let mut uses = vec![0usize; args.len()];
+    for i in resolved.iter().flatten() {
+        uses[*i] += 1;
+    }
+
+    if let Some(i) = uses.iter().position(|uses| *uses == 0) {
+        let msg = if args[i].name.is_some() {
+            "named argument never used"
+        } else {
+            "argument never used"
+        };
+        return parse::Error::new(args[i].expr.span(), msg).to_compile_error();
+    }
+
+    // Arguments are evaluated once, before anything is written, and bound in a match
+    // like `format_args!` does, so temporaries live until the end of output.
+    // An argument that is used once is moved, so it does not have to be `Copy`.
+    // Other arguments are bound by reference and formatted through it.
+    // Local names use mixed site hygiene, so they cannot clash with captured variables.
+    let names: Vec<Ident> = (0..args.len())
+        .map(|i| format_ident!("__arg{}", i, span = Span::mixed_site()))
+        .collect();
+    let exprs = args.iter().zip(&uses).map(|(arg, uses)| {
+        let expr = &arg.expr;
+        if *uses == 1 {
+            quote!(#expr)
         } else {
-            let arg = &input.args[i];
-            exprs.push(quote!(nano_fmt::NanoDisplay::fmt(#arg, #formatter);));
-            i += 1;
+            quote!(&#expr)
+        }
+    });
+
+    let mut resolved = resolved.into_iter();
+    let mut writes = vec![];
+    for piece in pieces {
+        match piece {
+            Piece::Str(s) => {
+                let pstr = mk_pstr(&s);
+                writes.push(quote!(nano_fmt::NanoDisplay::#fmt(#pstr, #formatter)));
+            }
+            Piece::Display(arg_ref, spec) => {
+                let value = match resolved.next().flatten() {
+                    Some(i) => {
+                        let name = &names[i];
+                        // Hexadecimal formatting is only implemented for integers.
+                        if uses[i] > 1 && spec.kind != Kind::Display {
+                            quote!((*#name))
+                        } else {
+                            quote!(#name)
+                        }
+                    }
+                    None => {
+                        // Capture a variable from the scope of the format string.
+                        let ArgRef::Name(name) = arg_ref else {
+                            unreachable!()
+                        };
+                        let name = Ident::new(name, span);
+                        quote!(#name)
+                    }
+                };
+                writes.push(mk_display(&value, &spec, formatter, fallible));
+            }
         }
     }
 
+    if line_end {
+        writes.push(quote!(nano_fmt::NanoDisplay::#fmt(progmem::LINE_END, #formatter)));
+    }
+
+    if !fallible {
+        return quote!({
+            match (#(#exprs,)*) {
+                (#(#names,)*) => {
+                    #(#writes;)*
+                }
+            }
+        });
+    }
+
+    // Return the first error from a labeled block.
+    let label = Lifetime::new("'__try_write", Span::mixed_site());
+    let err = Ident::new("__err", Span::mixed_site());
     quote!({
-        #(#exprs)*
+        #label: {
+            match (#(#exprs,)*) {
+                (#(#names,)*) => {
+                    #(
+                        if let ::core::result::Result::Err(#err) = #writes {
+                            break #label ::core::result::Result::Err(#err);
+                        }
+                    )*
+                    ::core::result::Result::Ok(())
+                }
+            }
+        }
     })
-    .into()
+}
+
+/// Check that named arguments follow positional ones and have unique names.
+fn check_names(args: &[&Argument]) -> parse::Result<()> {
+    let mut named = false;
+
+    for (i, arg) in args.iter().enumerate() {
+        match &arg.name {
+            Some(name) => {
+                if args[..i].iter().any(|arg| arg.name.as_ref() == Some(name)) {
+                    return Err(parse::Error::new(
+                        name.span(),
+                        format!("duplicate argument named `{name}`"),
+                    ));
+                }
+                named = true;
+            }
+            None if named => {
+                return Err(parse::Error::new(
+                    arg.expr.span(),
+                    "positional arguments cannot follow named arguments",
+                ));
+            }
+            None => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// Find the index of the argument referenced by a placeholder.
+///
+/// Returns `None` for names that do not match any named argument,
+/// such names are captured from the scope.
+fn resolve(
+    arg_ref: ArgRef<'_>,
+    args: &[&Argument],
+    next: &mut usize,
+    span: Span,
+) -> parse::Result<Option<usize>> {
+    let index = match arg_ref {
+        ArgRef::Next => {
+            *next += 1;
+            *next - 1
+        }
+        ArgRef::Index(index) => index,
+        ArgRef::Name(name) => {
+            return Ok(args
+                .iter()
+                .position(|arg| arg.name.as_ref().is_some_and(|n| n == name)));
+        }
+    };
+
+    if index < args.len() {
+        Ok(Some(index))
+    } else {
+        let supplied = args.len();
+        Err(parse::Error::new(
+            span,
+            format!(
+                "format string references argument {} but {} {} supplied",
+                index,
+                supplied,
+                if supplied == 1 { "was" } else { "were" }
+            ),
+        ))
+    }
+}
+
+fn mk_display(
+    arg: &proc_macro2::TokenStream,
+    spec: &Spec,
+    formatter: &Expr,
+    fallible: bool,
+) -> proc_macro2::TokenStream {
+    let mut value = match spec.kind {
+        Kind::Display => quote!(#arg),
+        Kind::LowerHex => quote!(nano_fmt::Hex::lower(#arg)),
+        Kind::UpperHex => quote!(nano_fmt::Hex::upper(#arg)),
+    };
+
+    if spec.alternate {
+        value = quote!(#value.with_prefix());
+    }
+
+    if !spec.is_padded() {
+        return if fallible {
+            quote!(nano_fmt::NanoDisplay::try_fmt(#value, #formatter))
+        } else {
+            quote!(nano_fmt::NanoDisplay::fmt(#value, #formatter))
+        };
+    }
+
+    let width = spec.width.unwrap_or(0);
+    let align = match spec.align {
+        None => quote!(None),
+        Some(Align::Left) => quote!(Some(nano_fmt::Align::Left)),
+        Some(Align::Right) => quote!(Some(nano_fmt::Align::Right)),
+    };
+    let zero = spec.zero;
+    let fmt_padded = if fallible {
+        quote!(try_fmt_padded)
+    } else {
+        quote!(fmt_padded)
+    };
+
+    quote!(nano_fmt::#fmt_padded(
+        #value,
+        nano_fmt::Spec {
+            width: #width,
+            align: #align,
+            zero: #zero,
+        },
+        #formatter,
+    ))
+}
+
+/// Parse the contents of `{...}`.
+fn parse_placeholder(s: &str, span: Span) -> parse::Result<(ArgRef<'_>, Spec)> {
+    let arg = match s.find(':') {
+        Some(pos) => &s[..pos],
+        None => s,
+    };
+
+    let arg_ref = if arg.is_empty() {
+        ArgRef::Next
+    } else if arg.bytes().all(|b| b.is_ascii_digit()) {
+        ArgRef::Index(
+            arg.parse()
+                .map_err(|_| parse::Error::new(span, "invalid argument index"))?,
+        )
+    } else if is_identifier(arg) {
+        ArgRef::Name(arg)
+    } else {
+        return Err(parse::Error::new(
+            span,
+            format!("invalid format string: `{arg}` is not an argument name or index"),
+        ));
+    };
+
+    Ok((arg_ref, parse_spec(&s[arg.len()..], span)?))
+}
+
+/// Returns `true` if `s` is a valid ASCII identifier, `_` is not accepted.
+fn is_identifier(s: &str) -> bool {
+    let mut bytes = s.bytes();
+    match bytes.next() {
+        Some(b'_') => s.len() > 1 && bytes.all(|b| b.is_ascii_alphanumeric() || b == b'_'),
+        Some(b) if b.is_ascii_alphabetic() => bytes.all(|b| b.is_ascii_alphanumeric() || b == b'_'),
+        _ => false,
+    }
+}
+
+/// Parse the format specifier part of a placeholder, starting with `:`.
+fn parse_spec(s: &str, span: Span) -> parse::Result<Spec> {
+    const ERR: &str = "invalid format string: expected `{}` or `{:[<|>][#][0][width][x|X]}`";
+
+    let mut spec = Spec::default();
+    if s.is_empty() {
+        return Ok(spec);
+    }
+
+    let Some(mut s) = s.strip_prefix(':') else {
+        return Err(parse::Error::new(span, ERR));
+    };
+
+    if let Some(rest) = s.strip_prefix('<') {
+        spec.align = Some(Align::Left);
+        s = rest;
+    } else if let Some(rest) = s.strip_prefix('>') {
+        spec.align = Some(Align::Right);
+        s = rest;
+    }
+
+    if let Some(rest) = s.strip_prefix('#') {
+        spec.alternate = true;
+        s = rest;
+    }
+
+    if let Some(rest) = s.strip_prefix('0') {
+        spec.zero = true;
+        s = rest;
+    }
+
+    let digits = s.bytes().take_while(u8::is_ascii_digit).count();
+    if digits > 0 {
+        let width = s[..digits]
+            .parse()
+            .map_err(|_| parse::Error::new(span, "format width is too large"))?;
+        spec.width = Some(width);
+        s = &s[digits..];
+    }
+
+    spec.kind = match s {
+        "" => Kind::Display,
+        "x" => Kind::LowerHex,
+        "X" => Kind::UpperHex,
+        _ => return Err(parse::Error::new(span, ERR)),
+    };
+
+    if spec.alternate && spec.kind == Kind::Display {
+        return Err(parse::Error::new(span, ERR));
+    }
+
+    Ok(spec)
 }
 
 // `}}` -> `}`
-fn unescape(mut literal: &str, span: Span) -> parse::Result<Cow<str>> {
+fn unescape(mut literal: &str, span: Span) -> parse::Result<Cow<'_, str>> {
     if literal.contains('}') {
         let mut buf = String::new();
 
@@ -177,7 +704,7 @@ fn unescape(mut literal: &str, span: Span) -> parse::Result<Cow<str>> {
     }
 }
 
-fn parse(mut literal: &str, span: Span) -> parse::Result<Vec<Piece>> {
+fn parse(mut literal: &str, span: Span) -> parse::Result<Vec<Piece<'_>>> {
     let mut pieces = vec![];
 
     let mut buf = String::new();
@@ -203,11 +730,17 @@ fn parse(mut literal: &str, span: Span) -> parse::Result<Vec<Piece>> {
             }
 
             (head, Some(tail)) => {
-                const DISPLAY: &str = "}";
                 const ESCAPED_BRACE: &str = "{";
 
                 let head = head.unwrap_or("");
-                if let Some(tail_tail) = tail.strip_prefix(DISPLAY) {
+                if let Some(tail_tail) = tail.strip_prefix(ESCAPED_BRACE) {
+                    buf.push_str(&unescape(head, span)?);
+                    buf.push('{');
+
+                    literal = tail_tail;
+                } else if let Some((placeholder, tail_tail)) = tail.split_once('}') {
+                    let (arg_ref, spec) = parse_placeholder(placeholder, span)?;
+
                     if buf.is_empty() {
                         if !head.is_empty() {
                             pieces.push(Piece::Str(unescape(head, span)?));
@@ -218,12 +751,7 @@ fn parse(mut literal: &str, span: Span) -> parse::Result<Vec<Piece>> {
                         pieces.push(Piece::Str(Cow::Owned(mem::take(&mut buf))));
                     }
 
-                    pieces.push(Piece::Display);
-
-                    literal = tail_tail;
-                } else if let Some(tail_tail) = tail.strip_prefix(ESCAPED_BRACE) {
-                    buf.push_str(&unescape(head, span)?);
-                    buf.push('{');
+                    pieces.push(Piece::Display(arg_ref, spec));
 
                     literal = tail_tail;
                 } else {
@@ -245,7 +773,7 @@ mod tests {
 
     use proc_macro2::Span;
 
-    use crate::Piece;
+    use crate::{Align, ArgRef, Kind, Piece, Spec};
 
     #[test]
     fn pieces() {
@@ -256,7 +784,7 @@ mod tests {
             super::parse("The answer is {}", span).ok(),
             Some(vec![
                 Piece::Str(Cow::Borrowed("The answer is ")),
-                Piece::Display
+                Piece::Display(ArgRef::Next, Spec::default())
             ]),
         );
 
@@ -264,13 +792,28 @@ mod tests {
 
         assert!(super::parse("{:#?}", span).is_err());
 
-        assert!(super::parse("{:x}", span).is_err());
-
-        assert!(super::parse("{:9x}", span).is_err());
-
-        assert!(super::parse("{:9X}", span).is_err());
-
-        assert!(super::parse("{:#X}", span).is_err());
+        // format specifiers
+        assert_eq!(
+            super::parse("{:x} {:9X}", span).ok(),
+            Some(vec![
+                Piece::Display(
+                    ArgRef::Next,
+                    Spec {
+                        kind: Kind::LowerHex,
+                        ..Spec::default()
+                    }
+                ),
+                Piece::Str(Cow::Borrowed(" ")),
+                Piece::Display(
+                    ArgRef::Next,
+                    Spec {
+                        width: Some(9),
+                        kind: Kind::UpperHex,
+                        ..Spec::default()
+                    }
+                ),
+            ]),
+        );
 
         // escaped braces
         assert_eq!(
@@ -284,6 +827,104 @@ mod tests {
         assert!(super::parse("{ ", span).is_err());
         assert!(super::parse("{ {", span).is_err());
         assert!(super::parse("{:q}", span).is_err());
+        assert!(super::parse("{x.y}", span).is_err());
+        assert!(super::parse("{_}", span).is_err());
+        assert!(super::parse("{-1}", span).is_err());
+
+        // argument references
+        assert_eq!(
+            super::parse("{cpm} {1:5} {_x}", span).ok(),
+            Some(vec![
+                Piece::Display(ArgRef::Name("cpm"), Spec::default()),
+                Piece::Str(Cow::Borrowed(" ")),
+                Piece::Display(
+                    ArgRef::Index(1),
+                    Spec {
+                        width: Some(5),
+                        ..Spec::default()
+                    }
+                ),
+                Piece::Str(Cow::Borrowed(" ")),
+                Piece::Display(ArgRef::Name("_x"), Spec::default()),
+            ]),
+        );
+    }
+
+    #[test]
+    fn spec() {
+        let span = Span::call_site();
+
+        assert_eq!(super::parse_spec("", span).ok(), Some(Spec::default()));
+        assert_eq!(
+            super::parse_spec(":5", span).ok(),
+            Some(Spec {
+                width: Some(5),
+                ..Spec::default()
+            })
+        );
+        assert_eq!(
+            super::parse_spec(":05", span).ok(),
+            Some(Spec {
+                zero: true,
+                width: Some(5),
+                ..Spec::default()
+            })
+        );
+        assert_eq!(
+            super::parse_spec(":#X", span).ok(),
+            Some(Spec {
+                alternate: true,
+                kind: Kind::UpperHex,
+                ..Spec::default()
+            })
+        );
+        assert_eq!(
+            super::parse_spec(":<8", span).ok(),
+            Some(Spec {
+                align: Some(Align::Left),
+                width: Some(8),
+                ..Spec::default()
+            })
+        );
+        assert_eq!(
+            super::parse_spec(":>#010x", span).ok(),
+            Some(Spec {
+                align: Some(Align::Right),
+                alternate: true,
+                zero: true,
+                width: Some(10),
+                kind: Kind::LowerHex,
+            })
+        );
+
+        assert!(super::parse_spec(":", span).is_ok());
+        assert!(super::parse_spec("5", span).is_err());
+        assert!(super::parse_spec(":#", span).is_err());
+        assert!(super::parse_spec(":256", span).is_err());
+        assert!(super::parse_spec(":^5", span).is_err());
+        assert!(super::parse_spec(":5x ", span).is_err());
+    }
+
+    #[test]
+    fn stats_record() {
+        assert_eq!(
+            super::stats_record("src/main.rs", 42, 5, "__pstr_0123456789abcdef_5", "CPS,"),
+            "src/main.rs:42\t5\t__pstr_0123456789abcdef_5\tCPS,\n"
+        );
+        assert_eq!(
+            super::stats_record("a.rs", 1, 4, "s", "\r\n\\"),
+            "a.rs:1\t4\ts\t\\r\\n\\\\\n"
+        );
+        assert_eq!(
+            super::stats_record("a.rs", 1, 31, "s", "mightyohm.com Geiger Counter"),
+            "a.rs:1\t31\ts\tmightyohm.com Geiger Cou...\n"
+        );
+    }
+
+    #[test]
+    fn asm_string() {
+        assert_eq!(super::asm_string("a\"b\\c.rs"), "a\\\"b\\\\c.rs");
+        assert_eq!(super::asm_string("\t\n\0µ"), "\\t\\n\\000\\302\\265");
     }
 
     #[test]
@@ -0,0 +1,352 @@
+//! Writers that wrap other writers.
+
+use crate::{NanoWrite, TryNanoWrite};
+
+/// Writer that sends output to two writers.
+pub struct Tee<A, B> {
+    a: A,
+    b: B,
+}
+
+impl<A, B> Tee<A, B> {
+    /// Create a writer that writes to both `a` and `b`.
+    #[must_use]
+    pub const fn new(a: A, b: B) -> Self {
+        Self { a, b }
+    }
+
+    /// Returns the wrapped writers.
+    pub fn into_inner(self) -> (A, B) {
+        (self.a, self.b)
+    }
+}
+
+impl<A: NanoWrite, B: NanoWrite> NanoWrite for Tee<A, B> {
+    fn write_byte(&mut self, b: u8) {
+        self.a.write_byte(b);
+        self.b.write_byte(b);
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.a.write_bytes(bytes);
+        self.b.write_bytes(bytes);
+    }
+}
+
+impl<A, B> TryNanoWrite for Tee<A, B>
+where
+    A: TryNanoWrite,
+    B: TryNanoWrite<Error = A::Error>,
+{
+    type Error = A::Error;
+
+    /// Write a byte to both writers.
+    ///
+    /// The second writer is not used if the first one fails.
+    fn try_write_byte(&mut self, b: u8) -> Result<(), Self::Error> {
+        self.a.try_write_byte(b)?;
+        self.b.try_write_byte(b)
+    }
+}
+
+/// Writer that counts written bytes.
+pub struct Counting<W> {
+    inner: W,
+    count: usize,
+}
+
+impl<W> Counting<W> {
+    /// Wrap a writer.
+    #[must_use]
+    pub const fn new(inner: W) -> Self {
+        Self { inner, count: 0 }
+    }
+
+    /// Returns the number of bytes written so far.
+    #[must_use]
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Returns the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: NanoWrite> NanoWrite for Counting<W> {
+    fn write_byte(&mut self, b: u8) {
+        self.inner.write_byte(b);
+        self.count += 1;
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.inner.write_bytes(bytes);
+        self.count += bytes.len();
+    }
+}
+
+impl<W: TryNanoWrite> TryNanoWrite for Counting<W> {
+    type Error = W::Error;
+
+    /// Write a byte, only successfully written bytes are counted.
+    fn try_write_byte(&mut self, b: u8) -> Result<(), Self::Error> {
+        self.inner.try_write_byte(b)?;
+        self.count += 1;
+        Ok(())
+    }
+}
+
+/// Writer that calculates XOR of written bytes, as used by NMEA 0183 sentences.
+pub struct XorChecksum<W> {
+    inner: W,
+    checksum: u8,
+}
+
+impl<W> XorChecksum<W> {
+    /// Wrap a writer.
+    #[must_use]
+    pub const fn new(inner: W) -> Self {
+        Self { inner, checksum: 0 }
+    }
+
+    /// Returns the checksum of bytes written since creation or the last reset.
+    #[must_use]
+    pub fn checksum(&self) -> u8 {
+        self.checksum
+    }
+
+    /// Reset the checksum to zero.
+    pub fn reset(&mut self) {
+        self.checksum = 0;
+    }
+
+    /// Returns a mutable reference to the wrapped writer.
+    ///
+    /// Bytes written directly to the wrapped writer are not included in the checksum.
+    pub fn inner_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: NanoWrite> NanoWrite for XorChecksum<W> {
+    fn write_byte(&mut self, b: u8) {
+        self.inner.write_byte(b);
+        self.checksum ^= b;
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.inner.write_bytes(bytes);
+        self.checksum = bytes.iter().fold(self.checksum, |checksum, b| checksum ^ b);
+    }
+}
+
+impl<W: TryNanoWrite> TryNanoWrite for XorChecksum<W> {
+    type Error = W::Error;
+
+    /// Write a byte, only successfully written bytes are included in the checksum.
+    fn try_write_byte(&mut self, b: u8) -> Result<(), Self::Error> {
+        self.inner.try_write_byte(b)?;
+        self.checksum ^= b;
+        Ok(())
+    }
+}
+
+/// Writer that calculates CRC-16/CCITT-FALSE of written bytes.
+///
+/// Polynomial is 0x1021, initial value is 0xFFFF.
+pub struct Crc16<W> {
+    inner: W,
+    crc: u16,
+}
+
+impl<W> Crc16<W> {
+    /// Initial value of the CRC.
+    const INIT: u16 = 0xFFFF;
+
+    /// Wrap a writer.
+    #[must_use]
+    pub const fn new(inner: W) -> Self {
+        Self {
+            inner,
+            crc: Self::INIT,
+        }
+    }
+
+    /// Returns the CRC of bytes written since creation or the last reset.
+    #[must_use]
+    pub fn crc(&self) -> u16 {
+        self.crc
+    }
+
+    /// Reset the CRC to the initial value.
+    pub fn reset(&mut self) {
+        self.crc = Self::INIT;
+    }
+
+    /// Returns a mutable reference to the wrapped writer.
+    ///
+    /// Bytes written directly to the wrapped writer are not included in the CRC.
+    pub fn inner_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Returns the wrapped writer.
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    fn update(&mut self, b: u8) {
+        self.crc ^= u16::from(b) << 8;
+        for _ in 0..8 {
+            self.crc = if self.crc & 0x8000 != 0 {
+                (self.crc << 1) ^ 0x1021
+            } else {
+                self.crc << 1
+            };
+        }
+    }
+}
+
+impl<W: NanoWrite> NanoWrite for Crc16<W> {
+    fn write_byte(&mut self, b: u8) {
+        self.inner.write_byte(b);
+        self.update(b);
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.inner.write_bytes(bytes);
+        for &b in bytes {
+            self.update(b);
+        }
+    }
+}
+
+impl<W: TryNanoWrite> TryNanoWrite for Crc16<W> {
+    type Error = W::Error;
+
+    /// Write a byte, only successfully written bytes are included in the CRC.
+    fn try_write_byte(&mut self, b: u8) -> Result<(), Self::Error> {
+        self.inner.try_write_byte(b)?;
+        self.update(b);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ArrayWriter, Hex, NanoDisplay, Overflow, Spec, fmt_padded, testing::Chunks};
+
+    #[test]
+    fn tee() {
+        let mut w = Tee::new(ArrayWriter::<8>::new(), ArrayWriter::<4>::new());
+        "CPS, ".fmt(&mut w);
+        42u8.fmt(&mut w);
+        let (a, b) = w.into_inner();
+        assert_eq!(a.as_bytes(), b"CPS, 42");
+        assert_eq!(b.as_bytes(), b"CPS,");
+        assert!(b.overflowed());
+
+        let mut w = Tee::new(ArrayWriter::<8>::new(), ArrayWriter::<2>::new());
+        assert_eq!(w.try_write_str("CPS"), Err(Overflow));
+        let (a, b) = w.into_inner();
+        assert_eq!(a.as_bytes(), b"CPS");
+        assert_eq!(b.as_bytes(), b"CP");
+    }
+
+    #[test]
+    fn counting() {
+        let mut w = Counting::new(ArrayWriter::<4>::new());
+        w.write_str("CPS");
+        12345u16.fmt(&mut w);
+        assert_eq!(w.count(), 8);
+
+        let mut w = Counting::new(ArrayWriter::<4>::new());
+        assert_eq!(12345u16.try_fmt(&mut w), Err(Overflow));
+        assert_eq!(w.count(), 4);
+    }
+
+    /// Write an NMEA 0183 sentence with its checksum.
+    fn nmea<W: NanoWrite>(w: &mut W, body: &str) {
+        w.write_byte(b'$');
+        let mut w = XorChecksum::new(w);
+        w.write_str(body);
+        let checksum = w.checksum();
+
+        let w = w.into_inner();
+        w.write_byte(b'*');
+        let spec = Spec {
+            width: 2,
+            align: None,
+            zero: true,
+        };
+        fmt_padded(Hex::upper(checksum), spec, w);
+    }
+
+    #[test]
+    fn xor_checksum() {
+        let mut w = XorChecksum::new(ArrayWriter::<8>::new());
+        w.write_str("GPGLL");
+        assert_eq!(w.checksum(), b'G' ^ b'P' ^ b'G' ^ b'L' ^ b'L');
+        w.reset();
+        assert_eq!(w.checksum(), 0);
+
+        let mut buf = ArrayWriter::<64>::new();
+        nmea(&mut buf, "GPGLL,5300.97914,N,00259.98174,E,125926,A");
+        assert_eq!(
+            buf.as_bytes(),
+            b"$GPGLL,5300.97914,N,00259.98174,E,125926,A*28"
+        );
+
+        // Checksums below 0x10 are padded to two digits.
+        let mut buf = ArrayWriter::<16>::new();
+        nmea(&mut buf, "AB");
+        assert_eq!(buf.as_bytes(), b"$AB*03");
+    }
+
+    #[test]
+    fn checksums_pass_slices_through() {
+        let mut w = XorChecksum::new(Chunks::new());
+        w.write_str("GPGLL");
+        w.write_byte(b',');
+        assert_eq!(w.checksum(), b'G' ^ b'P' ^ b'G' ^ b'L' ^ b'L' ^ b',');
+        assert_eq!(w.into_inner().chunks, [&b"GPGLL"[..], b","]);
+
+        let mut w = Crc16::new(Chunks::new());
+        w.write_str("123456789");
+        assert_eq!(w.crc(), 0x29B1);
+        assert_eq!(w.into_inner().chunks, [b"123456789"]);
+    }
+
+    #[test]
+    fn borrowed_writer() {
+        let mut buf = ArrayWriter::<8>::new();
+        let mut w = Counting::new(&mut buf);
+        "CPS".fmt(&mut w);
+        assert_eq!(w.count(), 3);
+        assert_eq!(w.try_write_str(", 42"), Ok(()));
+        assert_eq!(w.count(), 7);
+        assert_eq!(buf.as_bytes(), b"CPS, 42");
+    }
+
+    #[test]
+    fn crc16() {
+        let mut w = Crc16::new(ArrayWriter::<16>::new());
+        assert_eq!(w.crc(), 0xFFFF);
+        123_456_789u32.fmt(&mut w);
+        assert_eq!(w.crc(), 0x29B1);
+
+        // Only bytes that fit into the buffer are included.
+        w.reset();
+        assert_eq!(w.try_write_str("1234567890"), Err(Overflow));
+        let mut expected = Crc16::new(ArrayWriter::<16>::new());
+        expected.write_str("1234567");
+        assert_eq!(w.crc(), expected.crc());
+    }
+}
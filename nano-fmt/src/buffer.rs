@@ -0,0 +1,217 @@
+//! In-memory writers.
+
+use crate::{NanoWrite, TryNanoWrite};
+
+/// Error returned when a buffer has no space left.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Overflow;
+
+/// Writer that stores output in a fixed size array.
+///
+/// Bytes that do not fit are discarded and the overflow is recorded.
+pub struct ArrayWriter<const N: usize> {
+    data: [u8; N],
+    len: usize,
+    overflow: bool,
+}
+
+impl<const N: usize> ArrayWriter<N> {
+    /// Create a new empty writer.
+    #[allow(clippy::new_without_default)]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            data: [0; N],
+            len: 0,
+            overflow: false,
+        }
+    }
+
+    /// Returns the written bytes.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+
+    /// Returns the number of written bytes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if nothing was written.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if some bytes did not fit into the buffer.
+    #[must_use]
+    pub fn overflowed(&self) -> bool {
+        self.overflow
+    }
+
+    /// Discard the written bytes and reset the overflow flag.
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.overflow = false;
+    }
+}
+
+impl<const N: usize> NanoWrite for ArrayWriter<N> {
+    fn write_byte(&mut self, b: u8) {
+        let _ = self.try_write_byte(b);
+    }
+}
+
+impl<const N: usize> TryNanoWrite for ArrayWriter<N> {
+    type Error = Overflow;
+
+    fn try_write_byte(&mut self, b: u8) -> Result<(), Overflow> {
+        push(&mut self.data, &mut self.len, &mut self.overflow, b)
+    }
+}
+
+/// Writer that stores output in a borrowed slice.
+///
+/// Bytes that do not fit are discarded and the overflow is recorded.
+pub struct SliceWriter<'a> {
+    data: &'a mut [u8],
+    len: usize,
+    overflow: bool,
+}
+
+impl<'a> SliceWriter<'a> {
+    /// Create a new writer that writes to the beginning of `data`.
+    #[must_use]
+    pub fn new(data: &'a mut [u8]) -> Self {
+        Self {
+            data,
+            len: 0,
+            overflow: false,
+        }
+    }
+
+    /// Returns the written bytes.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.data[..self.len]
+    }
+
+    /// Consume the writer and return the written part of the slice.
+    #[must_use]
+    pub fn into_bytes(self) -> &'a [u8] {
+        &self.data[..self.len]
+    }
+
+    /// Returns the number of written bytes.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if nothing was written.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if some bytes did not fit into the slice.
+    #[must_use]
+    pub fn overflowed(&self) -> bool {
+        self.overflow
+    }
+
+    /// Discard the written bytes and reset the overflow flag.
+    pub fn clear(&mut self) {
+        self.len = 0;
+        self.overflow = false;
+    }
+}
+
+impl NanoWrite for SliceWriter<'_> {
+    fn write_byte(&mut self, b: u8) {
+        let _ = self.try_write_byte(b);
+    }
+}
+
+impl TryNanoWrite for SliceWriter<'_> {
+    type Error = Overflow;
+
+    fn try_write_byte(&mut self, b: u8) -> Result<(), Overflow> {
+        push(self.data, &mut self.len, &mut self.overflow, b)
+    }
+}
+
+/// Store `b` at `len` in `data`, recording an overflow if there is no space.
+fn push(data: &mut [u8], len: &mut usize, overflow: &mut bool, b: u8) -> Result<(), Overflow> {
+    if let Some(elem) = data.get_mut(*len) {
+        *elem = b;
+        *len += 1;
+        Ok(())
+    } else {
+        *overflow = true;
+        Err(Overflow)
+    }
+}
+
+#[cfg(any(feature = "alloc", test))]
+impl NanoWrite for alloc::vec::Vec<u8> {
+    fn write_byte(&mut self, b: u8) {
+        self.push(b);
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.extend_from_slice(bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::NanoDisplay;
+
+    #[test]
+    fn array_writer() {
+        let mut w = ArrayWriter::<8>::new();
+        assert!(w.is_empty());
+
+        12345u16.fmt(&mut w);
+        assert_eq!(w.as_bytes(), b"12345");
+        assert_eq!(w.len(), 5);
+        assert!(!w.overflowed());
+
+        "CPM, ".fmt(&mut w);
+        assert_eq!(w.as_bytes(), b"12345CPM");
+        assert!(w.overflowed());
+
+        w.clear();
+        assert!(w.is_empty());
+        assert!(!w.overflowed());
+
+        let mut w = ArrayWriter::<2>::new();
+        assert_eq!(w.try_write_str("ab"), Ok(()));
+        assert_eq!(w.try_write_byte(b'c'), Err(Overflow));
+        assert_eq!(w.as_bytes(), b"ab");
+    }
+
+    #[test]
+    fn slice_writer() {
+        let mut buf = [0; 4];
+        let mut w = SliceWriter::new(&mut buf);
+        assert_eq!(42u8.try_fmt(&mut w), Ok(()));
+        assert_eq!(w.as_bytes(), b"42");
+        assert_eq!(12345u16.try_fmt(&mut w), Err(Overflow));
+        assert!(w.overflowed());
+        assert_eq!(w.len(), 4);
+        assert_eq!(w.into_bytes(), b"4212");
+    }
+
+    #[test]
+    fn vec_writer() {
+        let mut v = alloc::vec::Vec::new();
+        "CPS, ".fmt(&mut v);
+        42u8.fmt(&mut v);
+        assert_eq!(v, b"CPS, 42");
+    }
+}
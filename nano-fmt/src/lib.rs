@@ -1,22 +1,423 @@
 #![no_std]
 
+#[cfg(any(feature = "alloc", test))]
+extern crate alloc;
+
+use core::{convert::Infallible, num::NonZeroU8};
+
+mod adapter;
+mod buffer;
+mod parse;
+#[cfg(test)]
+mod testing;
+
+pub use adapter::{Counting, Crc16, Tee, XorChecksum};
+pub use buffer::{ArrayWriter, Overflow, SliceWriter};
+pub use parse::{NanoParse, ParseError, parse_decimal};
+
 /// Writer trait for resource constrained systems.
+///
+/// Only [`NanoWrite::write_byte`] has to be implemented, other methods can be
+/// overridden by writers that handle multiple bytes more efficiently.
 pub trait NanoWrite {
     /// Write a byte to the writer.
     fn write_byte(&mut self, b: u8);
+
+    /// Write all bytes of a slice.
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.write_byte(b);
+        }
+    }
+
+    /// Write a string.
+    fn write_str(&mut self, s: &str) {
+        self.write_bytes(s.as_bytes());
+    }
+
+    /// Write bytes of a nul-terminated string, such as `PStr`.
+    fn write_zstr<I: IntoIterator<Item = NonZeroU8>>(&mut self, s: I) {
+        for b in s {
+            self.write_byte(b.get());
+        }
+    }
+}
+
+/// Fallible writer trait for resource constrained systems.
+pub trait TryNanoWrite {
+    /// Error returned when a byte cannot be written.
+    type Error;
+
+    /// Write a byte to the writer.
+    fn try_write_byte(&mut self, b: u8) -> Result<(), Self::Error>;
+
+    /// Write all bytes of a slice, stopping at the first error.
+    fn try_write_bytes(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        for &b in bytes {
+            self.try_write_byte(b)?;
+        }
+        Ok(())
+    }
+
+    /// Write a string, stopping at the first error.
+    fn try_write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        self.try_write_bytes(s.as_bytes())
+    }
+
+    /// Write bytes of a nul-terminated string, such as `PStr`, stopping at the first error.
+    fn try_write_zstr<I: IntoIterator<Item = NonZeroU8>>(
+        &mut self,
+        s: I,
+    ) -> Result<(), Self::Error> {
+        for b in s {
+            self.try_write_byte(b.get())?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: NanoWrite + ?Sized> NanoWrite for &mut W {
+    fn write_byte(&mut self, b: u8) {
+        (**self).write_byte(b);
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        (**self).write_bytes(bytes);
+    }
+
+    fn write_str(&mut self, s: &str) {
+        (**self).write_str(s);
+    }
+
+    fn write_zstr<I: IntoIterator<Item = NonZeroU8>>(&mut self, s: I) {
+        (**self).write_zstr(s);
+    }
+}
+
+impl<W: TryNanoWrite + ?Sized> TryNanoWrite for &mut W {
+    type Error = W::Error;
+
+    fn try_write_byte(&mut self, b: u8) -> Result<(), Self::Error> {
+        (**self).try_write_byte(b)
+    }
+
+    fn try_write_bytes(&mut self, bytes: &[u8]) -> Result<(), Self::Error> {
+        (**self).try_write_bytes(bytes)
+    }
+
+    fn try_write_str(&mut self, s: &str) -> Result<(), Self::Error> {
+        (**self).try_write_str(s)
+    }
+
+    fn try_write_zstr<I: IntoIterator<Item = NonZeroU8>>(
+        &mut self,
+        s: I,
+    ) -> Result<(), Self::Error> {
+        (**self).try_write_zstr(s)
+    }
 }
 
 /// Display trait for resource constrained systems.
 pub trait NanoDisplay {
+    /// Alignment used when the value is padded and no alignment is requested.
+    const ALIGN: Align = Align::Left;
+
     /// Write formatted representation of `self` to `f`.
     fn fmt<F: NanoWrite>(self, f: &mut F);
+
+    /// Write formatted representation of `self` to a fallible writer.
+    ///
+    /// Returns the first error reported by `f`. The default implementation
+    /// discards the remaining output after an error, implementations should
+    /// override it to stop formatting early.
+    fn try_fmt<F: TryNanoWrite>(self, f: &mut F) -> Result<(), F::Error>
+    where
+        Self: Sized,
+    {
+        let mut w = Latch { f, result: Ok(()) };
+        self.fmt(&mut w);
+        w.result
+    }
+
+    /// Returns the number of leading bytes, like a sign, that precede zero padding.
+    fn prefix_len(&self) -> u8 {
+        0
+    }
+}
+
+/// Alignment of a padded value.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Align {
+    Left,
+    Right,
+}
+
+/// Padding of a formatted value.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Spec {
+    /// Minimum width of the output.
+    pub width: u8,
+    /// Alignment of the value, `None` selects the default one for the type.
+    pub align: Option<Align>,
+    /// Pad with zeros after the prefix of the value, alignment is ignored.
+    pub zero: bool,
+}
+
+/// Write `value` to `f` padded according to `spec`.
+///
+/// The value is formatted twice, first to measure its length.
+pub fn fmt_padded<T, F>(value: T, spec: Spec, f: &mut F)
+where
+    T: NanoDisplay + Copy,
+    F: NanoWrite,
+{
+    // The output is only counted, an empty slice discards it.
+    let mut counter = Counting::new(SliceWriter::new(&mut []));
+    value.fmt(&mut counter);
+    let len = u8::try_from(counter.count()).unwrap_or(u8::MAX);
+    let mut padding = spec.width.saturating_sub(len);
+
+    if spec.zero {
+        let mut w = ZeroPad {
+            f,
+            prefix: value.prefix_len(),
+            padding,
+        };
+        value.fmt(&mut w);
+        padding = w.padding;
+        write_repeated(f, b'0', padding);
+        return;
+    }
+
+    match spec.align.unwrap_or(T::ALIGN) {
+        Align::Left => {
+            value.fmt(f);
+            write_repeated(f, b' ', padding);
+        }
+        Align::Right => {
+            write_repeated(f, b' ', padding);
+            value.fmt(f);
+        }
+    }
+}
+
+/// Fallible version of [`fmt_padded`].
+pub fn try_fmt_padded<T, F>(value: T, spec: Spec, f: &mut F) -> Result<(), F::Error>
+where
+    T: NanoDisplay + Copy,
+    F: TryNanoWrite,
+{
+    let mut w = Latch { f, result: Ok(()) };
+    fmt_padded(value, spec, &mut w);
+    w.result
+}
+
+/// Fallible writer that keeps the first error and discards the following output.
+struct Latch<'a, F: TryNanoWrite> {
+    f: &'a mut F,
+    result: Result<(), F::Error>,
+}
+
+impl<F: TryNanoWrite> NanoWrite for Latch<'_, F> {
+    fn write_byte(&mut self, b: u8) {
+        if self.result.is_ok() {
+            self.result = self.f.try_write_byte(b);
+        }
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        if self.result.is_ok() {
+            self.result = self.f.try_write_bytes(bytes);
+        }
+    }
+}
+
+/// Adapter that allows using a writer where a fallible one is expected.
+///
+/// Formatting code that is written once for fallible writers compiles
+/// to the same code as infallible one with this adapter.
+struct NoFail<'a, F>(&'a mut F);
+
+impl<F: NanoWrite> TryNanoWrite for NoFail<'_, F> {
+    type Error = Infallible;
+
+    #[inline(always)]
+    fn try_write_byte(&mut self, b: u8) -> Result<(), Infallible> {
+        self.0.write_byte(b);
+        Ok(())
+    }
+
+    #[inline(always)]
+    fn try_write_bytes(&mut self, bytes: &[u8]) -> Result<(), Infallible> {
+        self.0.write_bytes(bytes);
+        Ok(())
+    }
+}
+
+/// Write `b` to `f` `count` times.
+fn write_repeated<F: NanoWrite>(f: &mut F, b: u8, count: u8) {
+    for _ in 0..count {
+        f.write_byte(b);
+    }
+}
+
+/// Writer that inserts zeros after the first `prefix` bytes.
+struct ZeroPad<'a, F> {
+    f: &'a mut F,
+    prefix: u8,
+    padding: u8,
+}
+
+impl<F: NanoWrite> NanoWrite for ZeroPad<'_, F> {
+    fn write_byte(&mut self, b: u8) {
+        if self.prefix > 0 {
+            self.prefix -= 1;
+        } else {
+            write_repeated(self.f, b'0', self.padding);
+            self.padding = 0;
+        }
+        self.f.write_byte(b);
+    }
+}
+
+/// Hexadecimal formatting for integer types.
+pub trait NanoHex: Sized {
+    /// Write hexadecimal representation of `self` to `f` without a prefix.
+    ///
+    /// Negative values are written in two's complement.
+    fn try_fmt_hex<F: TryNanoWrite>(self, upper: bool, f: &mut F) -> Result<(), F::Error>;
+
+    /// Infallible version of [`NanoHex::try_fmt_hex`].
+    fn fmt_hex<F: NanoWrite>(self, upper: bool, f: &mut F) {
+        let Ok(()) = self.try_fmt_hex(upper, &mut NoFail(f));
+    }
+}
+
+/// Wrapper that displays an integer in hexadecimal.
+#[derive(Clone, Copy)]
+pub struct Hex<T> {
+    value: T,
+    upper: bool,
+    prefix: bool,
+}
+
+impl<T: NanoHex> Hex<T> {
+    /// Display `value` using lower case digits.
+    pub const fn lower(value: T) -> Self {
+        Self {
+            value,
+            upper: false,
+            prefix: false,
+        }
+    }
+
+    /// Display `value` using upper case digits.
+    pub const fn upper(value: T) -> Self {
+        Self {
+            value,
+            upper: true,
+            prefix: false,
+        }
+    }
+
+    /// Precede the digits with `0x`.
+    #[must_use]
+    pub const fn with_prefix(mut self) -> Self {
+        self.prefix = true;
+        self
+    }
+}
+
+impl<T: NanoHex> NanoDisplay for Hex<T> {
+    const ALIGN: Align = Align::Right;
+
+    fn fmt<F: NanoWrite>(self, f: &mut F) {
+        let Ok(()) = self.try_fmt(&mut NoFail(f));
+    }
+
+    fn try_fmt<F: TryNanoWrite>(self, f: &mut F) -> Result<(), F::Error> {
+        if self.prefix {
+            f.try_write_byte(b'0')?;
+            f.try_write_byte(b'x')?;
+        }
+        self.value.try_fmt_hex(self.upper, f)
+    }
+
+    fn prefix_len(&self) -> u8 {
+        if self.prefix { 2 } else { 0 }
+    }
+}
+
+/// Format arguments that are used more than once are formatted through a reference.
+impl<T: NanoDisplay + Copy> NanoDisplay for &T {
+    const ALIGN: Align = T::ALIGN;
+
+    fn fmt<F: NanoWrite>(self, f: &mut F) {
+        (*self).fmt(f);
+    }
+
+    fn try_fmt<F: TryNanoWrite>(self, f: &mut F) -> Result<(), F::Error> {
+        (*self).try_fmt(f)
+    }
+
+    fn prefix_len(&self) -> u8 {
+        (**self).prefix_len()
+    }
+}
+
+impl NanoDisplay for &str {
+    fn fmt<F: NanoWrite>(self, f: &mut F) {
+        f.write_str(self);
+    }
+
+    fn try_fmt<F: TryNanoWrite>(self, f: &mut F) -> Result<(), F::Error> {
+        f.try_write_str(self)
+    }
+}
+
+impl NanoDisplay for &[u8] {
+    fn fmt<F: NanoWrite>(self, f: &mut F) {
+        f.write_bytes(self);
+    }
+
+    fn try_fmt<F: TryNanoWrite>(self, f: &mut F) -> Result<(), F::Error> {
+        f.try_write_bytes(self)
+    }
+}
+
+impl NanoDisplay for char {
+    fn fmt<F: NanoWrite>(self, f: &mut F) {
+        let mut buf = [0; 4];
+        f.write_str(self.encode_utf8(&mut buf));
+    }
+
+    fn try_fmt<F: TryNanoWrite>(self, f: &mut F) -> Result<(), F::Error> {
+        let mut buf = [0; 4];
+        f.try_write_str(self.encode_utf8(&mut buf))
+    }
+}
+
+impl NanoDisplay for bool {
+    fn fmt<F: NanoWrite>(self, f: &mut F) {
+        f.write_str(if self { "true" } else { "false" });
+    }
+
+    fn try_fmt<F: TryNanoWrite>(self, f: &mut F) -> Result<(), F::Error> {
+        f.try_write_str(if self { "true" } else { "false" })
+    }
 }
 
 /// Implement `NanoDisplay` for an unsigned type.
 macro_rules! display_unsigned {
     ($ty:ident) => {
         impl $crate::NanoDisplay for $ty {
-            fn fmt<F: $crate::NanoWrite>(mut self, f: &mut F) {
+            const ALIGN: $crate::Align = $crate::Align::Right;
+
+            fn fmt<F: $crate::NanoWrite>(self, f: &mut F) {
+                let Ok(()) = self.try_fmt(&mut $crate::NoFail(f));
+            }
+
+            fn try_fmt<F: $crate::TryNanoWrite>(mut self, f: &mut F) -> Result<(), F::Error> {
                 const MAX_POW10: $ty = <$ty>::pow(10, $ty::MAX.ilog10() as u32);
 
                 let mut div = MAX_POW10;
@@ -33,9 +434,10 @@ macro_rules! display_unsigned {
 
                     if print || (div == 0) {
                         let b = b'0' + dig;
-                        f.write_byte(b);
+                        f.try_write_byte(b)?;
                     }
                 }
+                Ok(())
             }
         }
     };
@@ -47,3 +449,255 @@ display_unsigned!(u32);
 display_unsigned!(u64);
 display_unsigned!(u128);
 display_unsigned!(usize);
+
+/// Implement `NanoDisplay` for a signed type.
+macro_rules! display_signed {
+    ($ty:ident) => {
+        impl $crate::NanoDisplay for $ty {
+            const ALIGN: $crate::Align = $crate::Align::Right;
+
+            fn fmt<F: $crate::NanoWrite>(self, f: &mut F) {
+                let Ok(()) = self.try_fmt(&mut $crate::NoFail(f));
+            }
+
+            fn try_fmt<F: $crate::TryNanoWrite>(self, f: &mut F) -> Result<(), F::Error> {
+                if self < 0 {
+                    f.try_write_byte(b'-')?;
+                }
+
+                // `unsigned_abs` also handles `MIN` which has no positive counterpart.
+                self.unsigned_abs().try_fmt(f)
+            }
+
+            fn prefix_len(&self) -> u8 {
+                u8::from(*self < 0)
+            }
+        }
+    };
+}
+
+display_signed!(i8);
+display_signed!(i16);
+display_signed!(i32);
+display_signed!(i64);
+display_signed!(i128);
+display_signed!(isize);
+
+/// Implement `NanoHex` for an integer type using its unsigned counterpart.
+macro_rules! hex {
+    ($ty:ident, $uty:ident) => {
+        impl $crate::NanoHex for $ty {
+            fn try_fmt_hex<F: $crate::TryNanoWrite>(
+                self,
+                upper: bool,
+                f: &mut F,
+            ) -> Result<(), F::Error> {
+                let value = self as $uty;
+                let alpha = if upper { b'A' } else { b'a' };
+
+                // Number of digits, at least one digit is written for zero.
+                let digits = (value | 1).ilog2() / 4 + 1;
+
+                for i in (0..digits).rev() {
+                    let dig = ((value >> (i * 4)) & 0xF) as u8;
+                    let b = if dig < 10 {
+                        b'0' + dig
+                    } else {
+                        alpha + dig - 10
+                    };
+                    f.try_write_byte(b)?;
+                }
+                Ok(())
+            }
+        }
+    };
+}
+
+hex!(u8, u8);
+hex!(u16, u16);
+hex!(u32, u32);
+hex!(u64, u64);
+hex!(u128, u128);
+hex!(usize, usize);
+hex!(i8, u8);
+hex!(i16, u16);
+hex!(i32, u32);
+hex!(i64, u64);
+hex!(i128, u128);
+hex!(isize, usize);
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::{format, string::ToString, vec::Vec};
+
+    use super::*;
+    use crate::testing::{Limited, to_vec};
+
+    fn padded<T: NanoDisplay + Copy>(
+        value: T,
+        width: u8,
+        align: Option<Align>,
+        zero: bool,
+    ) -> Vec<u8> {
+        let mut buf = Vec::new();
+        let spec = Spec { width, align, zero };
+        fmt_padded(value, spec, &mut buf);
+        buf
+    }
+
+    /// Value that only implements `fmt`, so it uses the default `try_fmt`.
+    #[derive(Clone, Copy)]
+    struct Text(&'static [u8]);
+
+    impl NanoDisplay for Text {
+        fn fmt<F: NanoWrite>(self, f: &mut F) {
+            for &b in self.0 {
+                f.write_byte(b);
+            }
+        }
+    }
+
+    /// Check that `value` is formatted the same way as by `core::fmt`.
+    fn check<T: NanoDisplay + ToString + Copy>(value: T) {
+        let mut buf = Vec::new();
+        value.fmt(&mut buf);
+        assert_eq!(buf, value.to_string().into_bytes());
+    }
+
+    macro_rules! check_type {
+        ($ty:ident) => {
+            check::<$ty>(0);
+            check::<$ty>(1);
+            check::<$ty>(9);
+            check::<$ty>(10);
+            check::<$ty>(100);
+            check::<$ty>($ty::MAX);
+            check::<$ty>($ty::MAX - 1);
+            check::<$ty>($ty::MIN);
+        };
+    }
+
+    #[test]
+    fn unsigned() {
+        check_type!(u8);
+        check_type!(u16);
+        check_type!(u32);
+        check_type!(u64);
+        check_type!(u128);
+        check_type!(usize);
+    }
+
+    #[test]
+    fn signed() {
+        check_type!(i8);
+        check_type!(i16);
+        check_type!(i32);
+        check_type!(i64);
+        check_type!(i128);
+        check_type!(isize);
+
+        check::<i8>(-1);
+        check::<i16>(-10);
+        check::<i32>(-123_456);
+        check::<i64>(i64::MIN + 1);
+    }
+
+    #[test]
+    fn hex() {
+        assert_eq!(to_vec(Hex::lower(0u8)), b"0");
+        assert_eq!(to_vec(Hex::lower(0xABu8)), b"ab");
+        assert_eq!(to_vec(Hex::upper(0xABu8)), b"AB");
+        assert_eq!(to_vec(Hex::lower(0x1234u16).with_prefix()), b"0x1234");
+        assert_eq!(to_vec(Hex::lower(-1i16)), b"ffff");
+
+        for value in [0u32, 1, 0xF, 0x10, 0xDEAD_BEEF, u32::MAX] {
+            assert_eq!(to_vec(Hex::lower(value)), format!("{value:x}").into_bytes());
+            assert_eq!(to_vec(Hex::upper(value)), format!("{value:X}").into_bytes());
+        }
+        assert_eq!(
+            to_vec(Hex::lower(i128::MIN)),
+            format!("{:x}", i128::MIN).into_bytes()
+        );
+    }
+
+    #[test]
+    fn padding() {
+        assert_eq!(padded(42u8, 5, None, false), b"   42");
+        assert_eq!(padded(42u8, 5, Some(Align::Left), false), b"42   ");
+        assert_eq!(padded(42u8, 1, None, false), b"42");
+        assert_eq!(padded(42u8, 5, None, true), b"00042");
+        assert_eq!(padded(-42i8, 5, None, true), b"-0042");
+        assert_eq!(padded(-42i8, 5, Some(Align::Left), true), b"-0042");
+        assert_eq!(padded(-42i8, 5, None, false), b"  -42");
+        assert_eq!(
+            padded(Hex::lower(0x2Au8).with_prefix(), 6, None, true),
+            b"0x002a"
+        );
+        assert_eq!(padded(Hex::upper(0x2Au8), 4, None, false), b"  2A");
+    }
+
+    #[test]
+    fn try_fmt() {
+        let mut w = Limited::new(8);
+        assert_eq!(12345u16.try_fmt(&mut w), Ok(()));
+        assert_eq!((-42i8).try_fmt(&mut w), Ok(()));
+        assert_eq!(w.data, b"12345-42");
+
+        // Integers stop formatting at the first error.
+        let mut w = Limited::new(2);
+        assert_eq!(12345u32.try_fmt(&mut w), Err(()));
+        assert_eq!(w.data, b"12");
+        assert_eq!(w.attempts, 3);
+
+        let mut w = Limited::new(3);
+        assert_eq!(Hex::lower(0xBEEFu16).with_prefix().try_fmt(&mut w), Err(()));
+        assert_eq!(w.data, b"0xb");
+        assert_eq!(w.attempts, 4);
+
+        // Other values keep the first error and discard the rest.
+        let mut w = Limited::new(3);
+        assert_eq!(Text(b"Hello").try_fmt(&mut w), Err(()));
+        assert_eq!(w.data, b"Hel");
+        assert_eq!(w.attempts, 4);
+
+        let mut w = Limited::new(3);
+        let spec = Spec {
+            width: 5,
+            align: None,
+            zero: false,
+        };
+        assert_eq!(try_fmt_padded(42u8, spec, &mut w), Err(()));
+        assert_eq!(w.data, b"   ");
+    }
+
+    #[test]
+    fn bulk() {
+        let mut buf = Vec::new();
+        buf.write_bytes(b"CPS");
+        buf.write_str(", ");
+        buf.write_zstr(b"CPM".iter().map(|&b| NonZeroU8::new(b).unwrap()));
+        assert_eq!(buf, b"CPS, CPM");
+
+        let mut w = Limited::new(4);
+        assert_eq!(w.try_write_str("CPS, CPM"), Err(()));
+        assert_eq!(w.data, b"CPS,");
+        assert_eq!(w.attempts, 5);
+    }
+
+    #[test]
+    fn display_other() {
+        assert_eq!(to_vec("uSv/hr"), b"uSv/hr");
+        assert_eq!(to_vec(&b"CPM"[..]), b"CPM");
+        assert_eq!(to_vec('x'), b"x");
+        assert_eq!(to_vec('µ'), "µ".as_bytes());
+        assert_eq!(to_vec(true), b"true");
+        assert_eq!(to_vec(false), b"false");
+        assert_eq!(padded("ab", 4, None, false), b"ab  ");
+
+        let mut w = Limited::new(2);
+        assert_eq!("uSv".try_fmt(&mut w), Err(()));
+        assert_eq!(w.attempts, 3);
+    }
+}
@@ -0,0 +1,220 @@
+//! Parsing of numbers from text.
+
+/// Number parsing errors.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ParseError {
+    /// The input is empty.
+    Empty,
+    /// The input contains a character that is not allowed.
+    InvalidDigit,
+    /// The value does not fit into the target type.
+    Overflow,
+}
+
+/// Parsing of values from ASCII text.
+pub trait NanoParse: Sized {
+    /// Parse a value from `s`.
+    ///
+    /// The whole input has to be a valid value, surrounding spaces are not allowed.
+    fn parse_bytes(s: &[u8]) -> Result<Self, ParseError>;
+}
+
+/// Split an optional sign from the input.
+///
+/// Returns `true` if the number is negative.
+fn split_sign(s: &[u8]) -> Result<(bool, &[u8]), ParseError> {
+    match s {
+        [] => Err(ParseError::Empty),
+        [b'-', rest @ ..] => Ok((true, rest)),
+        [b'+', rest @ ..] => Ok((false, rest)),
+        _ => Ok((false, s)),
+    }
+}
+
+/// Convert an ASCII digit to its value.
+fn digit(b: u8) -> Result<u8, ParseError> {
+    if b.is_ascii_digit() {
+        Ok(b - b'0')
+    } else {
+        Err(ParseError::InvalidDigit)
+    }
+}
+
+/// Parse a decimal number with a fractional part.
+///
+/// Returns the value scaled by `10^digits`, for example, `"-1.5"` with 2
+/// digits is parsed as `-150`. Fractional digits beyond `digits` are
+/// truncated.
+pub fn parse_decimal(s: &[u8], digits: u8) -> Result<i32, ParseError> {
+    let (negative, s) = split_sign(s)?;
+
+    let (int, fract) = match s.iter().position(|&b| b == b'.') {
+        Some(i) => (&s[..i], &s[i + 1..]),
+        None => (s, &[][..]),
+    };
+    if int.is_empty() && fract.is_empty() {
+        return Err(ParseError::InvalidDigit);
+    }
+
+    // The value is accumulated as a negative number, so `i32::MIN` can be parsed.
+    let push = |value: i32, b: u8| {
+        let d = digit(b)?;
+        value
+            .checked_mul(10)
+            .and_then(|v| v.checked_sub(i32::from(d)))
+            .ok_or(ParseError::Overflow)
+    };
+
+    let mut value = 0;
+    for &b in int {
+        value = push(value, b)?;
+    }
+
+    let mut fract = fract.iter().copied();
+    for _ in 0..digits {
+        value = push(value, fract.next().unwrap_or(b'0'))?;
+    }
+    for b in fract {
+        digit(b)?;
+    }
+
+    if negative {
+        Ok(value)
+    } else {
+        value.checked_neg().ok_or(ParseError::Overflow)
+    }
+}
+
+/// Implement `NanoParse` for an unsigned type.
+macro_rules! parse_unsigned {
+    ($ty:ident) => {
+        impl $crate::NanoParse for $ty {
+            fn parse_bytes(s: &[u8]) -> Result<Self, ParseError> {
+                let (negative, s) = split_sign(s)?;
+                if negative || s.is_empty() {
+                    return Err(ParseError::InvalidDigit);
+                }
+
+                let mut value: $ty = 0;
+                for &b in s {
+                    let d = digit(b)?;
+                    value = value
+                        .checked_mul(10)
+                        .and_then(|v| v.checked_add($ty::from(d)))
+                        .ok_or(ParseError::Overflow)?;
+                }
+                Ok(value)
+            }
+        }
+    };
+}
+
+parse_unsigned!(u8);
+parse_unsigned!(u16);
+parse_unsigned!(u32);
+parse_unsigned!(u64);
+parse_unsigned!(u128);
+parse_unsigned!(usize);
+
+/// Implement `NanoParse` for a signed type.
+macro_rules! parse_signed {
+    ($ty:ident) => {
+        impl $crate::NanoParse for $ty {
+            fn parse_bytes(s: &[u8]) -> Result<Self, ParseError> {
+                let (negative, s) = split_sign(s)?;
+                if s.is_empty() {
+                    return Err(ParseError::InvalidDigit);
+                }
+
+                // The value is accumulated as a negative number, so `MIN` can be parsed.
+                let mut value: $ty = 0;
+                for &b in s {
+                    let d = digit(b)?;
+                    value = value
+                        .checked_mul(10)
+                        .and_then(|v| v.checked_sub($ty::from(d as i8)))
+                        .ok_or(ParseError::Overflow)?;
+                }
+
+                if negative {
+                    Ok(value)
+                } else {
+                    value.checked_neg().ok_or(ParseError::Overflow)
+                }
+            }
+        }
+    };
+}
+
+parse_signed!(i8);
+parse_signed!(i16);
+parse_signed!(i32);
+parse_signed!(i64);
+parse_signed!(i128);
+parse_signed!(isize);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unsigned() {
+        assert_eq!(u16::parse_bytes(b"0"), Ok(0));
+        assert_eq!(u16::parse_bytes(b"00042"), Ok(42));
+        assert_eq!(u16::parse_bytes(b"+7"), Ok(7));
+        assert_eq!(u16::parse_bytes(b"65535"), Ok(u16::MAX));
+        assert_eq!(u8::parse_bytes(b"255"), Ok(u8::MAX));
+        assert_eq!(u64::parse_bytes(b"18446744073709551615"), Ok(u64::MAX));
+
+        assert_eq!(u16::parse_bytes(b""), Err(ParseError::Empty));
+        assert_eq!(u16::parse_bytes(b"+"), Err(ParseError::InvalidDigit));
+        assert_eq!(u16::parse_bytes(b"-1"), Err(ParseError::InvalidDigit));
+        assert_eq!(u16::parse_bytes(b"12a"), Err(ParseError::InvalidDigit));
+        assert_eq!(u16::parse_bytes(b" 12"), Err(ParseError::InvalidDigit));
+        assert_eq!(u16::parse_bytes(b"65536"), Err(ParseError::Overflow));
+        assert_eq!(u8::parse_bytes(b"1000"), Err(ParseError::Overflow));
+    }
+
+    #[test]
+    fn signed() {
+        assert_eq!(i16::parse_bytes(b"0"), Ok(0));
+        assert_eq!(i16::parse_bytes(b"-0"), Ok(0));
+        assert_eq!(i16::parse_bytes(b"-42"), Ok(-42));
+        assert_eq!(i16::parse_bytes(b"+42"), Ok(42));
+        assert_eq!(i16::parse_bytes(b"32767"), Ok(i16::MAX));
+        assert_eq!(i16::parse_bytes(b"-32768"), Ok(i16::MIN));
+        assert_eq!(i8::parse_bytes(b"-128"), Ok(i8::MIN));
+
+        assert_eq!(i16::parse_bytes(b""), Err(ParseError::Empty));
+        assert_eq!(i16::parse_bytes(b"-"), Err(ParseError::InvalidDigit));
+        assert_eq!(i16::parse_bytes(b"--1"), Err(ParseError::InvalidDigit));
+        assert_eq!(i16::parse_bytes(b"1.5"), Err(ParseError::InvalidDigit));
+        assert_eq!(i16::parse_bytes(b"32768"), Err(ParseError::Overflow));
+        assert_eq!(i16::parse_bytes(b"-32769"), Err(ParseError::Overflow));
+    }
+
+    #[test]
+    fn decimal() {
+        assert_eq!(parse_decimal(b"0", 2), Ok(0));
+        assert_eq!(parse_decimal(b"12", 2), Ok(1200));
+        assert_eq!(parse_decimal(b"0.57", 2), Ok(57));
+        assert_eq!(parse_decimal(b"-1.5", 2), Ok(-150));
+        assert_eq!(parse_decimal(b".5", 2), Ok(50));
+        assert_eq!(parse_decimal(b"3.", 2), Ok(300));
+        assert_eq!(parse_decimal(b"0.0057", 4), Ok(57));
+        assert_eq!(parse_decimal(b"42", 0), Ok(42));
+        assert_eq!(parse_decimal(b"-21474836.48", 2), Ok(i32::MIN));
+
+        // Extra digits are truncated.
+        assert_eq!(parse_decimal(b"1.239", 2), Ok(123));
+        assert_eq!(parse_decimal(b"-1.239", 2), Ok(-123));
+
+        assert_eq!(parse_decimal(b"", 2), Err(ParseError::Empty));
+        assert_eq!(parse_decimal(b".", 2), Err(ParseError::InvalidDigit));
+        assert_eq!(parse_decimal(b"-", 2), Err(ParseError::InvalidDigit));
+        assert_eq!(parse_decimal(b"1.2.3", 2), Err(ParseError::InvalidDigit));
+        assert_eq!(parse_decimal(b"1.23x", 2), Err(ParseError::InvalidDigit));
+        assert_eq!(parse_decimal(b"21474836.48", 2), Err(ParseError::Overflow));
+        assert_eq!(parse_decimal(b"1", 10), Err(ParseError::Overflow));
+    }
+}
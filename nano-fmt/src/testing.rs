@@ -0,0 +1,63 @@
+//! Helpers shared by unit tests.
+
+use alloc::vec::Vec;
+
+use crate::{NanoDisplay, NanoWrite, TryNanoWrite};
+
+/// Returns formatted representation of `value`.
+pub fn to_vec<T: NanoDisplay>(value: T) -> Vec<u8> {
+    let mut buf = Vec::new();
+    value.fmt(&mut buf);
+    buf
+}
+
+/// Writer that records every write call as a separate chunk.
+pub struct Chunks {
+    pub chunks: Vec<Vec<u8>>,
+}
+
+impl Chunks {
+    pub fn new() -> Self {
+        Self { chunks: Vec::new() }
+    }
+}
+
+impl NanoWrite for Chunks {
+    fn write_byte(&mut self, b: u8) {
+        self.chunks.push(Vec::from([b]));
+    }
+
+    fn write_bytes(&mut self, bytes: &[u8]) {
+        self.chunks.push(Vec::from(bytes));
+    }
+}
+
+/// Writer that fails after `capacity` bytes, counting attempted writes.
+pub struct Limited {
+    pub data: Vec<u8>,
+    pub capacity: usize,
+    pub attempts: usize,
+}
+
+impl Limited {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            data: Vec::new(),
+            capacity,
+            attempts: 0,
+        }
+    }
+}
+
+impl TryNanoWrite for Limited {
+    type Error = ();
+
+    fn try_write_byte(&mut self, b: u8) -> Result<(), ()> {
+        self.attempts += 1;
+        if self.data.len() == self.capacity {
+            return Err(());
+        }
+        self.data.push(b);
+        Ok(())
+    }
+}
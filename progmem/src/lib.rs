@@ -1,19 +1,37 @@
 #![no_std]
-#![feature(asm_experimental_arch)]
+#![cfg_attr(target_arch = "avr", feature(asm_experimental_arch))]
 
 #[cfg(target_arch = "avr")]
 use core::arch::asm;
-use core::num::NonZeroU8;
+use core::{mem::MaybeUninit, num::NonZeroU8};
 
 use cfg_if::cfg_if;
-use nano_fmt::{NanoDisplay, NanoWrite};
-pub use nano_fmt_macro::{P, write};
+use nano_fmt::{NanoDisplay, NanoWrite, TryNanoWrite};
+pub use nano_fmt_macro::{P, try_write, try_writeln, write, writeln};
+
+// Allows using the macros inside this crate.
+extern crate self as progmem;
+
+/// Line terminator appended by [`writeln!`].
+///
+/// CRLF is used by default, `line-end-lf` or `line-end-cr` feature selects another one.
+/// The terminator is stored in program memory only once.
+#[cfg(feature = "line-end-lf")]
+pub const LINE_END: PStr = P!("\n");
+#[cfg(all(feature = "line-end-cr", not(feature = "line-end-lf")))]
+pub const LINE_END: PStr = P!("\r");
+#[cfg(not(any(feature = "line-end-lf", feature = "line-end-cr")))]
+pub const LINE_END: PStr = P!("\r\n");
 
 /// C-style string stored in program memory.
 /// It is only suitable for formatted output.
 #[derive(Clone, Copy)]
 pub struct PStr(*const u8);
 
+// SAFETY: The string is never modified.
+unsafe impl Send for PStr {}
+unsafe impl Sync for PStr {}
+
 impl PStr {
     /// Construct a new instance of a string.
     ///
@@ -22,9 +40,38 @@ impl PStr {
     /// The string should stay constant during program execution.
     /// On AVR, the string should reside in program memory.
     #[must_use]
-    pub unsafe fn new(ptr: *const u8) -> Self {
+    pub const unsafe fn new(ptr: *const u8) -> Self {
         Self(ptr)
     }
+
+    /// Returns the address of the string.
+    #[must_use]
+    pub const fn as_ptr(self) -> *const u8 {
+        self.0
+    }
+
+    /// Returns `true` if the string is equal to `other` ignoring ASCII case.
+    #[must_use]
+    pub fn eq_ignore_ascii_case(self, other: &[u8]) -> bool {
+        let mut other = other.iter();
+
+        for b in self {
+            match other.next() {
+                Some(o) if o.eq_ignore_ascii_case(&b.get()) => {}
+                _ => return false,
+            }
+        }
+
+        other.next().is_none()
+    }
+
+    /// Returns the first byte and the rest of the string, or `None` if the string is empty.
+    #[must_use]
+    pub fn split_first(self) -> Option<(NonZeroU8, Self)> {
+        let mut iter = self.into_iter();
+        let b = iter.next()?;
+        Some((b, Self(iter.0)))
+    }
 }
 
 impl IntoIterator for PStr {
@@ -57,8 +104,9 @@ impl Iterator for Iter {
                         options(pure, readonly, preserves_flags, nostack),
                     };
                 } else {
-                    b = *p;
-                    p = p.add(1);
+                    // Strings are stored in ordinary memory on other targets.
+                    b = *self.0;
+                    self.0 = self.0.add(1);
                 }
             }
         }
@@ -66,10 +114,333 @@ impl Iterator for Iter {
     }
 }
 
+/// Value stored in program memory.
+///
+/// The value can only be accessed by copying it to RAM using [`ProgMem::load`].
+#[repr(transparent)]
+pub struct ProgMem<T>(T);
+
+impl<T: Copy> ProgMem<T> {
+    /// Wrap a value.
+    ///
+    /// # Safety
+    /// On AVR, the created instance should be placed in program memory,
+    /// for example, by using `#[unsafe(link_section = ".progmem.data")]` on a static.
+    #[must_use]
+    pub const unsafe fn new(value: T) -> Self {
+        Self(value)
+    }
+
+    /// Copy the value from program memory.
+    #[must_use]
+    pub fn load(&self) -> T {
+        let mut value = MaybeUninit::<T>::uninit();
+        let src = (&raw const self.0).cast::<u8>();
+        let dst = value.as_mut_ptr().cast::<u8>();
+
+        for i in 0..size_of::<T>() {
+            // SAFETY: Both pointers are in bounds of the respective values.
+            unsafe { dst.add(i).write(load_byte(src.add(i))) };
+        }
+
+        // SAFETY: All bytes of the value were copied.
+        unsafe { value.assume_init() }
+    }
+}
+
+/// Read a byte from program memory.
+///
+/// # Safety
+/// `ptr` should be valid for reads. On AVR, it should point to program memory.
+unsafe fn load_byte(ptr: *const u8) -> u8 {
+    let b: u8;
+
+    unsafe {
+        cfg_if! {
+            if #[cfg(target_arch = "avr")] {
+                asm! {
+                    "lpm {b}, Z",
+                    b = out(reg) b,
+                    in("Z") ptr,
+                    options(pure, readonly, preserves_flags, nostack),
+                };
+            } else {
+                b = *ptr;
+            }
+        }
+    }
+    b
+}
+
 impl NanoDisplay for PStr {
     fn fmt<F: NanoWrite>(self, f: &mut F) {
-        for b in self {
-            f.write_byte(b.get());
+        f.write_zstr(self);
+    }
+
+    fn try_fmt<F: TryNanoWrite>(self, f: &mut F) -> Result<(), F::Error> {
+        f.try_write_zstr(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use nano_fmt::{ArrayWriter, NanoDisplay, NanoWrite, Overflow};
+
+    use crate::{LINE_END, P, PStr, ProgMem, try_write, try_writeln, write, writeln};
+
+    fn collect(s: PStr) -> Vec<u8> {
+        s.into_iter().map(|b| b.get()).collect()
+    }
+
+    #[test]
+    fn iter() {
+        assert_eq!(collect(P!("")), b"");
+        assert_eq!(collect(P!("Hello")), b"Hello");
+        assert_eq!(collect(P!("CPS, ")), b"CPS, ");
+    }
+
+    #[test]
+    fn split_first() {
+        assert!(P!("").split_first().is_none());
+
+        let (b, rest) = P!("OK").split_first().unwrap();
+        assert_eq!(b.get(), b'O');
+        assert_eq!(collect(rest), b"K");
+    }
+
+    // Strings are only interned where they are defined using assembly.
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn interned() {
+        fn first() -> PStr {
+            P!("CPS, ")
         }
+
+        fn second() -> PStr {
+            P!("CPS, ")
+        }
+
+        assert_eq!(first().as_ptr(), second().as_ptr());
+        assert_ne!(first().as_ptr(), P!("CPM, ").as_ptr());
+
+        let line_end = if cfg!(feature = "line-end-lf") {
+            P!("\n")
+        } else if cfg!(feature = "line-end-cr") {
+            P!("\r")
+        } else {
+            P!("\r\n")
+        };
+        assert_eq!(line_end.as_ptr(), LINE_END.as_ptr());
+    }
+
+    #[test]
+    fn eq_ignore_ascii_case() {
+        assert!(P!("").eq_ignore_ascii_case(b""));
+        assert!(P!("SBM-20").eq_ignore_ascii_case(b"SBM-20"));
+        assert!(P!("SBM-20").eq_ignore_ascii_case(b"sbm-20"));
+        assert!(!P!("SBM-20").eq_ignore_ascii_case(b"SBM-2"));
+        assert!(!P!("SBM-20").eq_ignore_ascii_case(b"SBM-200"));
+        assert!(!P!("SBM-20").eq_ignore_ascii_case(b""));
+    }
+
+    #[test]
+    fn prog_mem() {
+        #[derive(Clone, Copy)]
+        struct Profile {
+            name: PStr,
+            value: u16,
+        }
+
+        static PROFILES: [ProgMem<Profile>; 2] = unsafe {
+            [
+                ProgMem::new(Profile {
+                    name: P!("one"),
+                    value: 1,
+                }),
+                ProgMem::new(Profile {
+                    name: P!("two"),
+                    value: 2,
+                }),
+            ]
+        };
+
+        let profile = PROFILES[1].load();
+        assert_eq!(collect(profile.name), b"two");
+        assert_eq!(profile.value, 2);
+    }
+
+    #[test]
+    fn display() {
+        let mut buf = Vec::new();
+        P!("uSv/hr").fmt(&mut buf);
+        assert_eq!(buf, b"uSv/hr");
+    }
+
+    #[test]
+    fn write() {
+        let mut buf = Vec::new();
+        write!(
+            &mut buf,
+            "CPS, {}, CPM, {}, {}\r\n",
+            5u16,
+            300u32,
+            P!("SLOW")
+        );
+        assert_eq!(buf, b"CPS, 5, CPM, 300, SLOW\r\n");
+    }
+
+    #[test]
+    fn write_args() {
+        let cpm = 300u32;
+        let mut buf = Vec::new();
+        write!(
+            &mut buf,
+            "{cpm}, {0}, {1}, {0:>3}, {mode}, {cpm:05}",
+            5u16,
+            P!("uSv"),
+            mode = P!("SLOW")
+        );
+        assert_eq!(buf, b"300, 5, uSv,   5, SLOW, 00300");
+    }
+
+    #[test]
+    fn write_evaluates_args_once() {
+        let mut calls = 0;
+        let mut next = || {
+            calls += 1;
+            calls
+        };
+
+        let mut buf = Vec::new();
+        write!(&mut buf, "{0} {0} {1}", next(), next());
+        assert_eq!(buf, b"1 1 2");
+        assert_eq!(calls, 2);
+    }
+
+    #[test]
+    fn write_moves_args() {
+        struct Owned(Vec<u8>);
+
+        impl NanoDisplay for Owned {
+            fn fmt<F: NanoWrite>(self, f: &mut F) {
+                f.write_bytes(&self.0);
+            }
+        }
+
+        let mut buf = Vec::new();
+        write!(&mut buf, "{}, {}", Owned(b"CPM".to_vec()), 300u32);
+        assert_eq!(buf, b"CPM, 300");
+    }
+
+    #[test]
+    fn write_borrows_args_used_twice() {
+        struct Name(Vec<u8>);
+
+        impl NanoDisplay for &Name {
+            fn fmt<F: NanoWrite>(self, f: &mut F) {
+                f.write_bytes(&self.0);
+            }
+        }
+
+        let name = Name(b"SBM-20".to_vec());
+        let mut buf = Vec::new();
+        write!(&mut buf, "{0}{0}, {0:>8}", name);
+        assert_eq!(buf, b"SBM-20SBM-20,   SBM-20");
+        assert_eq!(name.0, b"SBM-20");
+    }
+
+    #[test]
+    fn write_borrows_temporaries() {
+        let mut buf = Vec::new();
+        write!(
+            &mut buf,
+            "{}, {}",
+            std::string::String::from("CPM").as_str(),
+            300u32
+        );
+        assert_eq!(buf, b"CPM, 300");
+    }
+
+    #[test]
+    fn writeln() {
+        let mut buf = Vec::new();
+        writeln!(&mut buf, "CPS, {}", 5u16);
+        writeln!(&mut buf);
+
+        let line_end = collect(LINE_END);
+        let mut expected = b"CPS, 5".to_vec();
+        expected.extend_from_slice(&line_end);
+        expected.extend_from_slice(&line_end);
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn try_write() {
+        let mut buf = ArrayWriter::<64>::new();
+        assert_eq!(
+            try_write!(&mut buf, "CPS, {}, CPM, {:5}, {}", 5u16, 300u32, P!("SLOW")),
+            Ok(())
+        );
+        assert_eq!(buf.as_bytes(), b"CPS, 5, CPM,   300, SLOW");
+
+        let mut buf = ArrayWriter::<64>::new();
+        assert_eq!(try_writeln!(&mut buf, "OK"), Ok(()));
+        assert_eq!(try_writeln!(&mut buf), Ok(()));
+        let mut expected = b"OK".to_vec();
+        expected.extend(collect(LINE_END));
+        expected.extend(collect(LINE_END));
+        assert_eq!(buf.as_bytes(), expected);
+
+        // Writing stops at the first error.
+        let mut calls = 0;
+        let mut next = || {
+            calls += 1;
+            calls
+        };
+        let mut buf = ArrayWriter::<7>::new();
+        let result = try_write!(&mut buf, "CPS, {}, CPM, {}", 12345u16, next());
+        assert_eq!(result, Err(Overflow));
+        assert_eq!(buf.as_bytes(), b"CPS, 12");
+        // Arguments are still evaluated.
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn concat() {
+        assert_eq!(collect(P!("Hello, " "world")), b"Hello, world");
+
+        let mut buf = Vec::new();
+        write!(
+            &mut buf,
+            "CPS, {}, "
+            "CPM, {}",
+            5u16,
+            300u32
+        );
+        assert_eq!(buf, b"CPS, 5, CPM, 300");
+    }
+
+    #[test]
+    fn write_spec() {
+        let mut buf = Vec::new();
+        write!(
+            &mut buf,
+            "[{:5}] [{:05}] [{:<4}] [{:>6}] [{:x}] [{:X}] [{:#06x}]",
+            42u16,
+            -42i16,
+            7u8,
+            P!("SLOW"),
+            0xBEEFu16,
+            0xBEEFu16,
+            0x2Au8
+        );
+        assert_eq!(
+            buf,
+            b"[   42] [-0042] [7   ] [  SLOW] [beef] [BEEF] [0x002a]"
+        );
     }
 }
@@ -0,0 +1,221 @@
+/// Dose rate alarm levels.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Level {
+    /// Count rate is below the warning threshold.
+    Normal,
+    /// Count rate reached the warning threshold.
+    Warning,
+    /// Count rate reached the alarm threshold.
+    Alarm,
+}
+
+/// Dose rate alarm with hysteresis.
+///
+/// The level is raised as soon as CPM reaches a threshold, but it is only
+/// lowered once CPM drops 1/8 below the threshold. This avoids toggling the
+/// alarm when CPM fluctuates around a threshold.
+pub struct Alarm {
+    level: Level,
+}
+
+impl Alarm {
+    /// Create a new alarm in normal state.
+    #[allow(clippy::new_without_default)]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            level: Level::Normal,
+        }
+    }
+
+    /// Update alarm level using the current CPM.
+    ///
+    /// Threshold value of zero disables the corresponding level.
+    pub fn update(&mut self, cpm: u32, warning_threshold: u16, alarm_threshold: u16) -> Level {
+        let warning_threshold = u32::from(warning_threshold);
+        let alarm_threshold = u32::from(alarm_threshold);
+
+        let rising = level(cpm, warning_threshold, alarm_threshold);
+        self.level = if rising >= self.level {
+            rising
+        } else {
+            let falling = level(cpm, lower(warning_threshold), lower(alarm_threshold));
+            falling.min(self.level)
+        };
+
+        self.level
+    }
+
+    /// Returns the current alarm level.
+    #[must_use]
+    pub fn level(&self) -> Level {
+        self.level
+    }
+}
+
+/// Number of watchdog ticks (about 16 ms each) a beep or a pause between beeps lasts.
+const BEEP_TICKS: u8 = 3;
+
+// The longest pattern must not reach the beeper bit.
+const _: () = assert!(3 * 2 * BEEP_TICKS < Signal::BEEPER);
+
+/// Beep pattern that signals the alarm level.
+///
+/// The pattern is advanced once every watchdog tick, so the main loop does not
+/// have to wait for the beeps to finish.
+pub struct Signal {
+    /// Number of ticks until the end of the pattern.
+    /// [`Signal::BEEPER`] bit is set to use the beeper in addition to the LED,
+    /// this saves a byte of RAM.
+    ticks: u8,
+}
+
+impl Signal {
+    /// Bit of `ticks` that enables the beeper.
+    const BEEPER: u8 = 0x80;
+
+    /// Create a new signal without an active pattern.
+    #[allow(clippy::new_without_default)]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { ticks: 0 }
+    }
+
+    /// Start the pattern for `level`, replacing the previous one.
+    ///
+    /// Warning uses one beep and alarm three beeps. `no_beep` mutes the
+    /// beeper for warnings, alarm is signaled even if the beeper is muted.
+    pub fn start(&mut self, level: Level, no_beep: bool) {
+        let beeps = match level {
+            Level::Normal => 0,
+            Level::Warning => 1,
+            Level::Alarm => 3,
+        };
+        self.ticks = beeps * 2 * BEEP_TICKS;
+        if level == Level::Alarm || !no_beep {
+            self.ticks |= Self::BEEPER;
+        }
+    }
+
+    /// Advance the pattern by one tick.
+    ///
+    /// Returns `Some(true)` if the outputs should be on and `Some(false)` if
+    /// they should be off during this tick, or `None` if no pattern is active.
+    pub fn tick(&mut self) -> Option<bool> {
+        let ticks = self.ticks & !Self::BEEPER;
+        if ticks == 0 {
+            return None;
+        }
+
+        self.ticks -= 1;
+        // Every beep is followed by a pause of the same length.
+        Some(((ticks - 1) / BEEP_TICKS) % 2 == 1)
+    }
+
+    /// Returns `true` if a pattern is being signaled.
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.ticks & !Self::BEEPER != 0
+    }
+
+    /// Returns `true` if the beeper should be used in addition to the LED.
+    #[must_use]
+    pub fn use_beeper(&self) -> bool {
+        self.ticks & Self::BEEPER != 0
+    }
+}
+
+/// Returns alarm level for given thresholds.
+fn level(cpm: u32, warning_threshold: u32, alarm_threshold: u32) -> Level {
+    if alarm_threshold != 0 && cpm >= alarm_threshold {
+        Level::Alarm
+    } else if warning_threshold != 0 && cpm >= warning_threshold {
+        Level::Warning
+    } else {
+        Level::Normal
+    }
+}
+
+/// Returns the threshold for lowering the alarm level.
+fn lower(threshold: u32) -> u32 {
+    // Keep disabled thresholds disabled.
+    if threshold == 0 {
+        0
+    } else {
+        (threshold - threshold / 8).max(1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::*;
+
+    #[test]
+    fn levels() {
+        let mut alarm = Alarm::new();
+        assert_eq!(alarm.level(), Level::Normal);
+
+        assert_eq!(alarm.update(99, 100, 200), Level::Normal);
+        assert_eq!(alarm.update(100, 100, 200), Level::Warning);
+        assert_eq!(alarm.update(200, 100, 200), Level::Alarm);
+        assert_eq!(alarm.update(10, 100, 200), Level::Normal);
+        assert_eq!(alarm.update(1000, 100, 200), Level::Alarm);
+    }
+
+    #[test]
+    fn hysteresis() {
+        let mut alarm = Alarm::new();
+
+        assert_eq!(alarm.update(200, 100, 200), Level::Alarm);
+        assert_eq!(alarm.update(199, 100, 200), Level::Alarm);
+        assert_eq!(alarm.update(175, 100, 200), Level::Alarm);
+        assert_eq!(alarm.update(174, 100, 200), Level::Warning);
+        assert_eq!(alarm.update(99, 100, 200), Level::Warning);
+        assert_eq!(alarm.update(88, 100, 200), Level::Warning);
+        assert_eq!(alarm.update(87, 100, 200), Level::Normal);
+        assert_eq!(alarm.update(99, 100, 200), Level::Normal);
+    }
+
+    /// Collect the pattern, one character per tick.
+    fn pattern(signal: &mut Signal) -> std::string::String {
+        core::iter::from_fn(|| signal.tick())
+            .map(|on| if on { '#' } else { '.' })
+            .collect()
+    }
+
+    #[test]
+    fn signal() {
+        let mut signal = Signal::new();
+        assert_eq!(signal.tick(), None);
+
+        signal.start(Level::Normal, false);
+        assert_eq!(pattern(&mut signal), "");
+
+        signal.start(Level::Warning, false);
+        assert!(signal.use_beeper());
+        assert_eq!(pattern(&mut signal), "###...");
+
+        signal.start(Level::Warning, true);
+        assert!(!signal.use_beeper());
+        assert_eq!(pattern(&mut signal), "###...");
+
+        signal.start(Level::Alarm, true);
+        assert!(signal.is_active());
+        assert!(signal.use_beeper());
+        assert_eq!(pattern(&mut signal), "###...###...###...");
+        assert!(!signal.is_active());
+        assert_eq!(signal.tick(), None);
+    }
+
+    #[test]
+    fn disabled() {
+        let mut alarm = Alarm::new();
+
+        assert_eq!(alarm.update(0, 0, 0), Level::Normal);
+        assert_eq!(alarm.update(u32::MAX, 0, 0), Level::Normal);
+        assert_eq!(alarm.update(u32::MAX, 100, 0), Level::Warning);
+        assert_eq!(alarm.update(u32::MAX, 0, 200), Level::Alarm);
+    }
+}
@@ -23,12 +23,23 @@ impl Beeper {
 
     /// Turns on the beeper.
     pub fn turn_on(&mut self) {
+        // 160 = toggle OCR0A every 160ms, period = 320us, freq= 3.125kHz
+        self.turn_on_with_period(160);
+    }
+
+    /// Turns on the beeper with the alarm tone.
+    pub fn turn_on_alarm(&mut self) {
+        // 100 = toggle OCR0A every 100us, period = 200us, freq = 5kHz
+        self.turn_on_with_period(100);
+    }
+
+    /// Turns on the beeper, toggling the output every `half_period` microseconds.
+    fn turn_on_with_period(&mut self, half_period: u8) {
         // enable OCR0A output on pin PB2
         self.timer.tccr0a.modify(|_, w| w.com0a().match_toggle());
         // Set prescaler to clk/8 (1Mhz) or 1us/count.
         self.timer.tccr0b.modify(|_, w| w.cs0().prescale_8());
-        // 160 = toggle OCR0A every 160ms, period = 320us, freq= 3.125kHz
-        self.timer.ocr0a.write(|w| w.bits(160));
+        self.timer.ocr0a.write(|w| w.bits(half_period));
     }
 
     /// Turns off the beeper.
@@ -0,0 +1,362 @@
+use nano_fmt::NanoParse;
+
+use crate::{config::Format, tube::Tube};
+
+/// Maximum length of a command line (excluding the line terminator).
+///
+/// This is the length of the longest command, `SET THRESHOLD 65535`.
+pub const LINE_SIZE: usize = 19;
+
+/// Length of the longest command keyword.
+const KEYWORD_SIZE: usize = 9;
+
+/// Commands accepted over the serial port.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Command {
+    /// Mute the beeper.
+    Mute,
+    /// Unmute the beeper.
+    Unmute,
+    /// Restart averaging.
+    Reset,
+    /// Reset total counts and accumulated dose.
+    ResetTotal,
+    /// Report current configuration.
+    GetConfig,
+    /// Set CPM threshold for fast averaging mode.
+    SetThreshold(u16),
+    /// Select GM tube used for dose rate conversion.
+    SetTube(Tube),
+    /// Set CPM threshold for dose rate warning.
+    SetWarning(u16),
+    /// Set CPM threshold for dose rate alarm.
+    SetAlarm(u16),
+    /// Set format of the periodic report.
+    SetFormat(Format),
+}
+
+/// Command parsing errors.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Error {
+    /// The line did not fit into the line buffer.
+    TooLong,
+    /// The command is not recognized.
+    Unknown,
+    /// The command argument is missing or invalid.
+    InvalidArgument,
+    /// The line arrived before the previous one was processed, so it was discarded.
+    Overrun,
+}
+
+impl Command {
+    /// Parse a command from a line of text.
+    ///
+    /// Commands are case insensitive and words are separated by single spaces.
+    pub fn parse(line: &[u8]) -> Result<Self, Error> {
+        let mut words = line.split(|&b| b == b' ');
+
+        let mut buf = [0; KEYWORD_SIZE];
+        let command = match upper(words.next(), &mut buf) {
+            b"MUTE" => Self::Mute,
+            b"UNMUTE" => Self::Unmute,
+            b"RESET" => match words.next() {
+                None => Self::Reset,
+                word => match upper(word, &mut buf) {
+                    b"TOTAL" => Self::ResetTotal,
+                    _ => return Err(Error::Unknown),
+                },
+            },
+            b"GET" => match upper(words.next(), &mut buf) {
+                b"CONFIG" => Self::GetConfig,
+                _ => return Err(Error::Unknown),
+            },
+            b"SET" => match upper(words.next(), &mut buf) {
+                b"THRESHOLD" => Self::SetThreshold(parse_u16(words.next())?),
+                b"TUBE" => Self::SetTube(parse_tube(words.next())?),
+                b"WARNING" => Self::SetWarning(parse_u16(words.next())?),
+                b"ALARM" => Self::SetAlarm(parse_u16(words.next())?),
+                b"FORMAT" => match upper(words.next(), &mut buf) {
+                    b"FULL" => Self::SetFormat(Format::Full),
+                    b"CLASSIC" => Self::SetFormat(Format::Classic),
+                    _ => return Err(Error::InvalidArgument),
+                },
+                _ => return Err(Error::Unknown),
+            },
+            _ => return Err(Error::Unknown),
+        };
+
+        if words.next().is_some() {
+            return Err(Error::InvalidArgument);
+        }
+
+        Ok(command)
+    }
+
+    /// Returns `true` if the command changes settings that are stored in EEPROM.
+    #[must_use]
+    pub fn changes_settings(self) -> bool {
+        match self {
+            Self::Mute
+            | Self::Unmute
+            | Self::SetThreshold(_)
+            | Self::SetTube(_)
+            | Self::SetWarning(_)
+            | Self::SetAlarm(_)
+            | Self::SetFormat(_) => true,
+            Self::Reset | Self::ResetTotal | Self::GetConfig => false,
+        }
+    }
+}
+
+/// Convert a keyword to upper case using `buf` as storage.
+///
+/// Words that do not fit into `buf` are returned as an empty slice, they cannot match any keyword.
+fn upper<'a>(word: Option<&[u8]>, buf: &'a mut [u8]) -> &'a [u8] {
+    match word {
+        Some(word) if word.len() <= buf.len() => {
+            let buf = &mut buf[..word.len()];
+            buf.copy_from_slice(word);
+            buf.make_ascii_uppercase();
+            buf
+        }
+        _ => &[],
+    }
+}
+
+/// Parse a decimal number.
+fn parse_u16(word: Option<&[u8]>) -> Result<u16, Error> {
+    let word = word.ok_or(Error::InvalidArgument)?;
+    u16::parse_bytes(word).map_err(|_| Error::InvalidArgument)
+}
+
+/// Parse a tube name.
+fn parse_tube(word: Option<&[u8]>) -> Result<Tube, Error> {
+    word.and_then(Tube::from_name).ok_or(Error::InvalidArgument)
+}
+
+/// Buffer collecting received characters into lines.
+#[derive(Clone, Copy)]
+pub struct LineBuffer {
+    data: [u8; LINE_SIZE],
+    len: u8,
+    /// Error of the line, the rest of the line is discarded.
+    error: Option<Error>,
+    state: State,
+}
+
+/// State of [`LineBuffer`].
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum State {
+    /// A line is being received.
+    Receiving,
+    /// A complete line is waiting to be taken.
+    Ready,
+    /// A complete line is waiting and the next line is being discarded.
+    Discarding,
+    /// A complete line is waiting and the next line was discarded.
+    Discarded,
+}
+
+impl LineBuffer {
+    /// Create a new empty buffer.
+    #[allow(clippy::new_without_default)]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            data: [0; LINE_SIZE],
+            len: 0,
+            error: None,
+            state: State::Receiving,
+        }
+    }
+
+    /// Add a received byte to the buffer.
+    ///
+    /// Returns `true` if a complete line became available. Lines that arrive
+    /// before the previous line is taken are discarded and reported as
+    /// [`Error::Overrun`] once it is taken.
+    pub fn push(&mut self, b: u8) -> bool {
+        let line_end = matches!(b, b'\r' | b'\n');
+
+        match self.state {
+            State::Receiving if line_end => {
+                if self.len != 0 || self.error.is_some() {
+                    self.state = State::Ready;
+                    return true;
+                }
+            }
+            State::Receiving if self.error.is_none() => {
+                if let Some(elem) = self.data.get_mut(self.len as usize) {
+                    *elem = b;
+                    self.len += 1;
+                } else {
+                    self.error = Some(Error::TooLong);
+                }
+            }
+            State::Receiving => {}
+            State::Ready | State::Discarded if !line_end => self.state = State::Discarding,
+            State::Discarding if line_end => self.state = State::Discarded,
+            State::Ready | State::Discarding | State::Discarded => {}
+        }
+
+        false
+    }
+
+    /// Returns `true` if a complete line is waiting to be taken.
+    #[must_use]
+    pub fn is_ready(&self) -> bool {
+        self.state != State::Receiving
+    }
+
+    /// Take the complete line and continue receiving the next one.
+    ///
+    /// If the next line was discarded, the buffer holds an [`Error::Overrun`]
+    /// line afterwards, which is ready once its line terminator is received.
+    pub fn take(&mut self) -> Self {
+        let line = *self;
+        *self = Self::new();
+
+        match line.state {
+            State::Discarding => self.error = Some(Error::Overrun),
+            State::Discarded => {
+                self.error = Some(Error::Overrun);
+                self.state = State::Ready;
+            }
+            State::Receiving | State::Ready => {}
+        }
+
+        line
+    }
+
+    /// Returns the received line.
+    pub fn line(&self) -> Result<&[u8], Error> {
+        match self.error {
+            Some(err) => Err(err),
+            None => Ok(&self.data[..self.len as usize]),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        assert_eq!(Command::parse(b"mute"), Ok(Command::Mute));
+        assert_eq!(Command::parse(b"RESET total"), Ok(Command::ResetTotal));
+        assert_eq!(Command::parse(b"GET CONFIG"), Ok(Command::GetConfig));
+        assert_eq!(
+            Command::parse(b"SET TUBE j305"),
+            Ok(Command::SetTube(Tube::J305))
+        );
+        assert_eq!(
+            Command::parse(b"set format classic"),
+            Ok(Command::SetFormat(Format::Classic))
+        );
+        assert_eq!(Command::parse(b"RESET ALL"), Err(Error::Unknown));
+        assert_eq!(Command::parse(b"MUTE NOW"), Err(Error::InvalidArgument));
+        assert_eq!(Command::parse(b"RESET  TOTAL"), Err(Error::Unknown));
+        assert_eq!(Command::parse(b" MUTE"), Err(Error::Unknown));
+        assert_eq!(Command::parse(b"MUTE "), Err(Error::InvalidArgument));
+    }
+
+    #[test]
+    fn changes_settings() {
+        assert!(Command::Unmute.changes_settings());
+        assert!(Command::SetTube(Tube::J305).changes_settings());
+        assert!(!Command::GetConfig.changes_settings());
+        assert!(!Command::Reset.changes_settings());
+        assert!(!Command::ResetTotal.changes_settings());
+    }
+
+    #[test]
+    fn parse_number() {
+        assert_eq!(
+            Command::parse(b"SET THRESHOLD 1000"),
+            Ok(Command::SetThreshold(1000))
+        );
+        assert_eq!(
+            Command::parse(b"SET ALARM 65535"),
+            Ok(Command::SetAlarm(u16::MAX))
+        );
+        assert_eq!(Command::parse(b"SET WARNING 0"), Ok(Command::SetWarning(0)));
+        assert_eq!(Command::parse(b"SET WARNING"), Err(Error::InvalidArgument));
+        assert_eq!(
+            Command::parse(b"SET ALARM 65536"),
+            Err(Error::InvalidArgument)
+        );
+        assert_eq!(Command::parse(b"SET ALARM -1"), Err(Error::InvalidArgument));
+        assert_eq!(
+            Command::parse(b"SET ALARM 1e3"),
+            Err(Error::InvalidArgument)
+        );
+    }
+
+    /// Push all bytes of `input`, returns `true` if a line became available.
+    fn push_all(buf: &mut LineBuffer, input: &[u8]) -> bool {
+        let mut ready = false;
+        for &b in input {
+            ready |= buf.push(b);
+        }
+        ready
+    }
+
+    #[test]
+    fn line_buffer() {
+        let mut buf = LineBuffer::new();
+        assert!(!buf.push(b'\n'));
+        assert!(!push_all(&mut buf, b"MUTE"));
+        assert!(buf.push(b'\r'));
+        assert!(!buf.push(b'\n'));
+        assert_eq!(buf.take().line(), Ok(&b"MUTE"[..]));
+        assert!(!buf.is_ready());
+    }
+
+    #[test]
+    fn longest_command_fits() {
+        let mut buf = LineBuffer::new();
+        assert!(push_all(&mut buf, b"SET THRESHOLD 65535\n"));
+        assert_eq!(
+            buf.take().line().and_then(Command::parse),
+            Ok(Command::SetThreshold(u16::MAX))
+        );
+    }
+
+    #[test]
+    fn too_long() {
+        let mut buf = LineBuffer::new();
+        for _ in 0..=LINE_SIZE {
+            buf.push(b'A');
+        }
+        assert!(buf.push(b'\n'));
+        assert_eq!(buf.take().line(), Err(Error::TooLong));
+
+        // The next line is received normally.
+        assert!(push_all(&mut buf, b"MUTE\n"));
+        assert_eq!(buf.take().line(), Ok(&b"MUTE"[..]));
+    }
+
+    #[test]
+    fn overrun() {
+        // The second line arrives before the first one is taken.
+        let mut buf = LineBuffer::new();
+        assert!(push_all(&mut buf, b"MUTE\r\nUNMUTE\r\n"));
+        assert_eq!(buf.take().line(), Ok(&b"MUTE"[..]));
+        assert!(buf.is_ready());
+        assert_eq!(buf.take().line(), Err(Error::Overrun));
+        assert!(!buf.is_ready());
+
+        // The first line is taken while the second one is being received.
+        let mut buf = LineBuffer::new();
+        assert!(push_all(&mut buf, b"MUTE\nUNM"));
+        assert_eq!(buf.take().line(), Ok(&b"MUTE"[..]));
+        assert!(!buf.is_ready());
+        assert!(!push_all(&mut buf, b"UTE"));
+        assert!(buf.push(b'\n'));
+        assert_eq!(buf.take().line(), Err(Error::Overrun));
+
+        assert!(push_all(&mut buf, b"UNMUTE\n"));
+        assert_eq!(buf.take().line(), Ok(&b"UNMUTE"[..]));
+    }
+}
@@ -0,0 +1,117 @@
+use crate::tube::Tube;
+
+/// Default CPM threshold for fast averaging mode.
+pub const DEFAULT_THRESHOLD: u16 = 1000;
+
+/// Default CPM threshold for dose rate warning.
+///
+/// Can be changed at build time with `GEIGER_WARNING_THRESHOLD` environment variable.
+pub const DEFAULT_WARNING_THRESHOLD: u16 =
+    parse_threshold(option_env!("GEIGER_WARNING_THRESHOLD"), 500);
+
+/// Default CPM threshold for dose rate alarm.
+///
+/// Can be changed at build time with `GEIGER_ALARM_THRESHOLD` environment variable.
+pub const DEFAULT_ALARM_THRESHOLD: u16 =
+    parse_threshold(option_env!("GEIGER_ALARM_THRESHOLD"), 2000);
+
+/// Parse a threshold given at build time, or return `default` if it is not set.
+const fn parse_threshold(value: Option<&str>, default: u16) -> u16 {
+    let Some(value) = value else {
+        return default;
+    };
+
+    let bytes = value.as_bytes();
+    assert!(!bytes.is_empty(), "threshold must not be empty");
+
+    let mut threshold: u32 = 0;
+    let mut i = 0;
+    while i < bytes.len() {
+        let digit = bytes[i];
+        assert!(digit.is_ascii_digit(), "threshold must be a decimal number");
+        threshold = threshold * 10 + (digit - b'0') as u32;
+        assert!(
+            threshold <= u16::MAX as u32,
+            "threshold must fit in 16 bits"
+        );
+        i += 1;
+    }
+
+    threshold as u16
+}
+
+/// Format of the periodic report.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum Format {
+    /// Report with all available fields.
+    Full,
+    /// Report compatible with the original firmware.
+    Classic,
+}
+
+impl Format {
+    /// Convert from a numeric representation.
+    #[must_use]
+    pub fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            0 => Some(Self::Full),
+            1 => Some(Self::Classic),
+            _ => None,
+        }
+    }
+}
+
+/// Runtime configuration that can be changed over the serial port.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Config {
+    /// CPM threshold for fast averaging mode.
+    pub threshold: u16,
+    /// GM tube used for dose rate conversion.
+    pub tube: Tube,
+    /// CPM threshold for dose rate warning, zero disables the warning.
+    pub warning_threshold: u16,
+    /// CPM threshold for dose rate alarm, zero disables the alarm.
+    pub alarm_threshold: u16,
+    /// Format of the periodic report.
+    pub format: Format,
+}
+
+impl Config {
+    /// Create a configuration with default values.
+    #[allow(clippy::new_without_default)]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            threshold: DEFAULT_THRESHOLD,
+            tube: Tube::DEFAULT,
+            warning_threshold: DEFAULT_WARNING_THRESHOLD,
+            alarm_threshold: DEFAULT_ALARM_THRESHOLD,
+            format: Format::Full,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn threshold() {
+        assert_eq!(parse_threshold(None, 500), 500);
+        assert_eq!(parse_threshold(Some("0"), 500), 0);
+        assert_eq!(parse_threshold(Some("65535"), 500), 65535);
+    }
+
+    #[test]
+    #[should_panic]
+    fn threshold_overflow() {
+        parse_threshold(Some("65536"), 500);
+    }
+
+    #[test]
+    #[should_panic]
+    fn threshold_not_a_number() {
+        parse_threshold(Some("1k"), 500);
+    }
+}
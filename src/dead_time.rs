@@ -0,0 +1,64 @@
+/// Number of microseconds in a second.
+const MICROS_PER_SECOND: u32 = 1_000_000;
+
+/// Resolution of the live time calculation in microseconds.
+///
+/// Using 16 us units allows `cps * MICROS_PER_SECOND / LIVE_TIME_UNIT` to fit into `u32`.
+const LIVE_TIME_UNIT: u32 = 16;
+
+/// Correct GM counts for the tube dead time.
+///
+/// Uses non-paralyzable model `N = n / (1 - n * t)`, where `n` is the measured
+/// count rate and `t` is the dead time. `cps` is the number of counts during the
+/// last second, `dead_time` is the dead time in microseconds. The result is
+/// saturated to `u16::MAX`.
+#[must_use]
+pub fn correct(cps: u16, dead_time: u16) -> u16 {
+    // Time during which the tube was not able to detect particles (n * t).
+    let dead = u32::from(cps) * u32::from(dead_time);
+    // Time during which the tube was able to detect particles (1 - n * t).
+    let live = MICROS_PER_SECOND.saturating_sub(dead) / LIVE_TIME_UNIT;
+
+    if live == 0 {
+        return u16::MAX;
+    }
+
+    let corrected = u32::from(cps) * (MICROS_PER_SECOND / LIVE_TIME_UNIT) / live;
+    corrected.try_into().unwrap_or(u16::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::correct;
+
+    #[test]
+    fn no_dead_time() {
+        assert_eq!(correct(0, 0), 0);
+        assert_eq!(correct(100, 0), 100);
+        assert_eq!(correct(u16::MAX, 0), u16::MAX);
+    }
+
+    #[test]
+    fn low_rate() {
+        assert_eq!(correct(0, 190), 0);
+        assert_eq!(correct(1, 190), 1);
+        assert_eq!(correct(10, 190), 10);
+    }
+
+    #[test]
+    fn high_rate() {
+        // 1000 / (1 - 1000 * 190e-6) = 1234.6
+        assert_eq!(correct(1000, 190), 1234);
+        // 2000 / (1 - 2000 * 90e-6) = 2439.0
+        assert_eq!(correct(2000, 90), 2439);
+        // 4000 / (1 - 4000 * 190e-6) = 16666.7
+        assert_eq!(correct(4000, 190), 16666);
+    }
+
+    #[test]
+    fn saturation() {
+        assert_eq!(correct(5000, 190), u16::MAX);
+        assert_eq!(correct(u16::MAX, 190), u16::MAX);
+        assert_eq!(correct(u16::MAX, u16::MAX), u16::MAX);
+    }
+}
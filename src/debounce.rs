@@ -0,0 +1,119 @@
+/// Number of consecutive equal samples required to accept a new button state.
+const SAMPLES: u8 = 2;
+
+/// Mask selecting the last `SAMPLES` samples.
+const MASK: u8 = (1 << SAMPLES) - 1;
+
+/// Bit that stores the debounced button state, it is not used by samples.
+const PRESSED: u8 = 0x80;
+const _: () = assert!(MASK & PRESSED == 0);
+
+/// Button debouncer driven by periodic sampling.
+#[derive(Clone, Copy)]
+pub struct Debouncer {
+    /// Recent samples, the newest one in the least significant bit.
+    /// The debounced button state is kept in [`PRESSED`] bit to save RAM.
+    samples: u8,
+}
+
+impl Debouncer {
+    /// Create a new instance with the button released.
+    #[allow(clippy::new_without_default)]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self::with_state(false)
+    }
+
+    /// Create a new instance with the given debounced button state.
+    ///
+    /// A button that is already held when sampling starts has to be released
+    /// before the next press is reported.
+    #[must_use]
+    pub const fn with_state(pressed: bool) -> Self {
+        Self {
+            samples: if pressed { PRESSED | MASK } else { 0 },
+        }
+    }
+
+    /// Add a new sample of the button state.
+    ///
+    /// Returns `true` if the button was just pressed.
+    pub fn update(&mut self, pressed: bool) -> bool {
+        self.samples = (self.samples & PRESSED) | ((self.samples << 1) & MASK) | u8::from(pressed);
+
+        match self.samples & MASK {
+            MASK if !self.is_pressed() => {
+                self.samples |= PRESSED;
+                true
+            }
+            0 => {
+                self.samples &= !PRESSED;
+                false
+            }
+            _ => false,
+        }
+    }
+
+    /// Returns the debounced button state.
+    #[must_use]
+    pub fn is_pressed(&self) -> bool {
+        self.samples & PRESSED != 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn press_and_release() {
+        let mut debouncer = Debouncer::new();
+
+        assert!(!debouncer.update(true));
+        assert!(debouncer.update(true));
+        assert!(debouncer.is_pressed());
+        assert!(!debouncer.update(true));
+
+        assert!(!debouncer.update(false));
+        assert!(debouncer.is_pressed());
+        assert!(!debouncer.update(false));
+        assert!(!debouncer.is_pressed());
+    }
+
+    #[test]
+    fn held_at_start() {
+        let mut debouncer = Debouncer::with_state(true);
+        assert!(debouncer.is_pressed());
+
+        for _ in 0..4 {
+            assert!(!debouncer.update(true));
+        }
+        assert!(debouncer.is_pressed());
+
+        assert!(!debouncer.update(false));
+        assert!(!debouncer.update(false));
+        assert!(!debouncer.is_pressed());
+
+        assert!(!debouncer.update(true));
+        assert!(debouncer.update(true));
+    }
+
+    #[test]
+    fn bounce_is_ignored() {
+        let mut debouncer = Debouncer::new();
+
+        for pressed in [true, false, true, false] {
+            assert!(!debouncer.update(pressed));
+        }
+        assert!(!debouncer.is_pressed());
+
+        assert!(!debouncer.update(true));
+        assert!(debouncer.update(true));
+
+        // Bounce on release does not produce another press.
+        for pressed in [false, true, false, true, true] {
+            assert!(!debouncer.update(pressed));
+        }
+        assert!(debouncer.is_pressed());
+    }
+}
@@ -0,0 +1,115 @@
+use crate::fixed::Fixed2;
+
+/// Number of dose units (uSv x10,000 / 60) in a `Fixed2` uSv unit.
+///
+/// One second at `cps` counts per second adds `cps * scale_factor` units.
+const UNITS_PER_FIXED2: u32 = 6000;
+
+/// Total GM counts and accumulated dose since boot or last reset.
+pub struct DoseAccumulator {
+    /// Total number of GM counts.
+    counts: u32,
+    /// Accumulated dose in uSv.
+    dose: Fixed2,
+    /// Part of the dose that is not yet added to `dose`, in uSv x10,000 / 60.
+    /// It is always less than `UNITS_PER_FIXED2`.
+    remainder: u16,
+}
+
+impl DoseAccumulator {
+    /// Create a new accumulator with zero counts.
+    #[allow(clippy::new_without_default)]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            counts: 0,
+            dose: Fixed2::ZERO,
+            remainder: 0,
+        }
+    }
+
+    /// Add counts for one second.
+    ///
+    /// `cps` is the number of counts during the last second, `corrected_cps` is
+    /// the count corrected for the tube dead time, and `scale_factor` is
+    /// [`TubeProfile::scale_factor`](crate::tube::TubeProfile::scale_factor) of the tube.
+    pub fn add(&mut self, cps: u16, corrected_cps: u16, scale_factor: u16) {
+        self.counts = self.counts.saturating_add(u32::from(cps));
+
+        let units = u32::from(self.remainder) + u32::from(corrected_cps) * u32::from(scale_factor);
+        // The quotient is at most 65535 * 65535 / 6000 + 1, it always fits.
+        let dose = Fixed2::from_bits((units / UNITS_PER_FIXED2) as i32);
+        self.dose = self.dose.saturating_add(dose);
+        self.remainder = (units % UNITS_PER_FIXED2) as u16;
+    }
+
+    /// Reset counts and dose to zero.
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Returns total number of GM counts.
+    #[must_use]
+    pub fn counts(&self) -> u32 {
+        self.counts
+    }
+
+    /// Returns accumulated dose in uSv.
+    #[must_use]
+    pub fn dose(&self) -> Fixed2 {
+        self.dose
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts() {
+        let mut acc = DoseAccumulator::new();
+        assert_eq!(acc.counts(), 0);
+
+        acc.add(10, 10, 57);
+        acc.add(u16::MAX, u16::MAX, 57);
+        assert_eq!(acc.counts(), 10 + u32::from(u16::MAX));
+
+        acc.reset();
+        assert_eq!(acc.counts(), 0);
+        assert_eq!(acc.dose(), Fixed2::from_bits(0));
+    }
+
+    #[test]
+    fn dose() {
+        let mut acc = DoseAccumulator::new();
+
+        // 100 CPS is 6000 CPM or 34.2 uSv/hr with SBM-20.
+        for _ in 0..3600 {
+            acc.add(100, 100, 57);
+        }
+        assert_eq!(acc.dose(), Fixed2::from_bits(3420));
+    }
+
+    #[test]
+    fn small_dose_is_not_lost() {
+        let mut acc = DoseAccumulator::new();
+
+        // 1 CPS is 60 CPM or 0.342 uSv/hr with SBM-20.
+        for _ in 0..36_000 {
+            acc.add(1, 1, 57);
+        }
+        assert_eq!(acc.dose(), Fixed2::from_bits(342));
+    }
+
+    #[test]
+    fn corrected_counts_are_used_for_dose() {
+        let mut acc = DoseAccumulator::new();
+
+        // 200 CPS is 12000 CPM or 72 uSv/hr, 1.2 uSv in a minute.
+        for _ in 0..60 {
+            acc.add(100, 200, 60);
+        }
+        assert_eq!(acc.counts(), 6000);
+        assert_eq!(acc.dose(), Fixed2::from_bits(120));
+    }
+}
@@ -0,0 +1,67 @@
+use avr_device::interrupt;
+
+use crate::hal::pac::EEPROM;
+
+/// Driver for the internal EEPROM.
+pub struct Eeprom {
+    p: EEPROM,
+}
+
+impl Eeprom {
+    /// Create new instance from raw hardware.
+    #[must_use]
+    pub fn new(p: EEPROM) -> Self {
+        Self { p }
+    }
+
+    /// Read `buf.len()` bytes starting at `address`.
+    pub fn read(&mut self, address: u8, buf: &mut [u8]) {
+        self.wait();
+
+        for (offset, b) in buf.iter_mut().enumerate() {
+            self.p.eear.write(|w| w.bits(address + offset as u8));
+            self.p.eecr.write(|w| w.eere().set_bit());
+            *b = self.p.eedr.read().bits();
+        }
+    }
+
+    /// Returns `true` if EEPROM content starting at `address` is equal to `data`.
+    pub fn contains(&mut self, address: u8, data: &[u8]) -> bool {
+        self.wait();
+
+        data.iter().enumerate().all(|(offset, &b)| {
+            self.p.eear.write(|w| w.bits(address + offset as u8));
+            self.p.eecr.write(|w| w.eere().set_bit());
+            self.p.eedr.read().bits() == b
+        })
+    }
+
+    /// Write `data` starting at `address`.
+    ///
+    /// Only bytes that differ from the current EEPROM content are written to
+    /// reduce EEPROM wear. Each written byte takes about 3.4 ms.
+    pub fn write(&mut self, address: u8, data: &[u8]) {
+        for (offset, &b) in data.iter().enumerate() {
+            let address = address + offset as u8;
+
+            self.wait();
+            self.p.eear.write(|w| w.bits(address));
+            self.p.eecr.write(|w| w.eere().set_bit());
+            if self.p.eedr.read().bits() == b {
+                continue;
+            }
+
+            self.p.eedr.write(|w| w.bits(b));
+            // EEPE has to be set within four clock cycles after EEMPE.
+            interrupt::free(|_| {
+                self.p.eecr.write(|w| w.eempe().set_bit());
+                self.p.eecr.write(|w| w.eempe().set_bit().eepe().set_bit());
+            });
+        }
+    }
+
+    /// Wait for completion of the previous write.
+    fn wait(&self) {
+        while self.p.eecr.read().eepe().bit_is_set() {}
+    }
+}
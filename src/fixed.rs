@@ -1,29 +1,341 @@
-use nano_fmt::{NanoDisplay, NanoWrite};
+use nano_fmt::{Align, NanoDisplay, NanoParse, NanoWrite, ParseError};
+
+/// Rounding mode used when precision is lost.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Rounding {
+    /// Round toward zero (truncate).
+    TowardZero,
+    /// Round toward negative infinity.
+    Floor,
+    /// Round toward positive infinity.
+    Ceil,
+    /// Round to the nearest value, halfway cases away from zero.
+    Nearest,
+}
+
+/// Signed fixed point value with `DIGITS` decimal digits after the point.
+///
+/// `DIGITS` can be at most 9.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub struct Fixed<const DIGITS: u8>(i32);
 
 /// Fixed point value with 2 decimal digits.
-#[derive(Clone, Copy)]
-pub struct Fixed2(u32);
+pub type Fixed2 = Fixed<2>;
+
+impl<const DIGITS: u8> Fixed<DIGITS> {
+    /// Scaled integer representing one.
+    const SCALE: i32 = {
+        assert!(DIGITS <= 9, "too many decimal digits");
+        10i32.pow(DIGITS as u32)
+    };
+
+    /// Zero value.
+    pub const ZERO: Self = Self(0);
+    /// Smallest representable value.
+    pub const MIN: Self = Self(i32::MIN);
+    /// Largest representable value.
+    pub const MAX: Self = Self(i32::MAX);
 
-impl Fixed2 {
     /// Construct new value from scaled integer.
     #[must_use]
-    pub const fn from_bits(bits: u32) -> Self {
+    pub const fn from_bits(bits: i32) -> Self {
         Self(bits)
     }
+
+    /// Returns the scaled integer.
+    #[must_use]
+    pub const fn to_bits(self) -> i32 {
+        self.0
+    }
+
+    /// Convert an integer, returns `None` on overflow.
+    #[must_use]
+    pub const fn from_int(value: i32) -> Option<Self> {
+        match value.checked_mul(Self::SCALE) {
+            Some(bits) => Some(Self(bits)),
+            None => None,
+        }
+    }
+
+    /// Returns the integer part, rounded using `rounding`.
+    #[must_use]
+    pub fn to_int(self, rounding: Rounding) -> i32 {
+        div_round(self.0, Self::SCALE, rounding)
+    }
+
+    /// Checked addition, returns `None` on overflow.
+    #[must_use]
+    pub fn checked_add(self, rhs: Self) -> Option<Self> {
+        self.0.checked_add(rhs.0).map(Self)
+    }
+
+    /// Saturating addition.
+    #[must_use]
+    pub fn saturating_add(self, rhs: Self) -> Self {
+        Self(self.0.saturating_add(rhs.0))
+    }
+
+    /// Checked subtraction, returns `None` on overflow.
+    #[must_use]
+    pub fn checked_sub(self, rhs: Self) -> Option<Self> {
+        self.0.checked_sub(rhs.0).map(Self)
+    }
+
+    /// Saturating subtraction.
+    #[must_use]
+    pub fn saturating_sub(self, rhs: Self) -> Self {
+        Self(self.0.saturating_sub(rhs.0))
+    }
+
+    /// Checked multiplication by an integer, returns `None` on overflow.
+    #[must_use]
+    pub fn checked_mul_int(self, rhs: i32) -> Option<Self> {
+        self.0.checked_mul(rhs).map(Self)
+    }
+
+    /// Saturating multiplication by an integer.
+    #[must_use]
+    pub fn saturating_mul_int(self, rhs: i32) -> Self {
+        Self(self.0.saturating_mul(rhs))
+    }
+
+    /// Checked division by an integer.
+    ///
+    /// Returns `None` if `rhs` is zero or the result overflows.
+    #[must_use]
+    pub fn checked_div_int(self, rhs: i32, rounding: Rounding) -> Option<Self> {
+        if rhs == 0 || (self.0 == i32::MIN && rhs == -1) {
+            return None;
+        }
+        Some(Self(div_round(self.0, rhs, rounding)))
+    }
+
+    /// Convert to a different number of decimal digits.
+    ///
+    /// Returns `None` if the value does not fit. `TO` is checked at compile time
+    /// the same way as `DIGITS`.
+    #[must_use]
+    pub fn rescale<const TO: u8>(self, rounding: Rounding) -> Option<Fixed<TO>> {
+        if TO >= DIGITS {
+            let factor = Fixed::<TO>::SCALE / Self::SCALE;
+            self.0.checked_mul(factor).map(Fixed)
+        } else {
+            let divisor = Self::SCALE / Fixed::<TO>::SCALE;
+            Some(Fixed(div_round(self.0, divisor, rounding)))
+        }
+    }
+
+    /// Convert to a different number of decimal digits, saturating on overflow.
+    #[must_use]
+    pub fn saturating_rescale<const TO: u8>(self, rounding: Rounding) -> Fixed<TO> {
+        self.rescale(rounding)
+            .unwrap_or(if self.0 < 0 { Fixed::MIN } else { Fixed::MAX })
+    }
 }
 
-impl NanoDisplay for Fixed2 {
+/// Divide `n` by `d` using `rounding`.
+///
+/// `d` should not be zero and the quotient should not overflow.
+fn div_round(n: i32, d: i32, rounding: Rounding) -> i32 {
+    let q = n / d;
+    let r = n % d;
+
+    if r == 0 {
+        return q;
+    }
+
+    // The exact quotient is positive if the signs are the same.
+    let positive = (n < 0) == (d < 0);
+
+    match rounding {
+        Rounding::TowardZero => q,
+        Rounding::Floor if positive => q,
+        Rounding::Floor => q - 1,
+        Rounding::Ceil if positive => q + 1,
+        Rounding::Ceil => q,
+        Rounding::Nearest => {
+            // `2 * |r| >= |d|` without overflow.
+            if r.unsigned_abs() >= d.unsigned_abs() - r.unsigned_abs() {
+                if positive { q + 1 } else { q - 1 }
+            } else {
+                q
+            }
+        }
+    }
+}
+
+impl<const DIGITS: u8> NanoDisplay for Fixed<DIGITS> {
+    const ALIGN: Align = Align::Right;
+
     fn fmt<F: NanoWrite>(self, f: &mut F) {
-        let fract = (self.0 % 100) as u8;
-        let integer = self.0 / 100;
+        if self.0 < 0 {
+            f.write_byte(b'-');
+        }
 
-        integer.fmt(f);
+        let bits = self.0.unsigned_abs();
+        let scale = Self::SCALE as u32;
 
-        f.write_byte(b'.');
+        (bits / scale).fmt(f);
 
-        if fract < 10 {
-            f.write_byte(b'0');
+        if DIGITS > 0 {
+            f.write_byte(b'.');
+
+            let fract = bits % scale;
+            let mut div = scale / 10;
+            while div > 0 {
+                f.write_byte(b'0' + (fract / div % 10) as u8);
+                div /= 10;
+            }
         }
-        u32::from(fract).fmt(f);
+    }
+
+    fn prefix_len(&self) -> u8 {
+        u8::from(self.0 < 0)
+    }
+}
+
+impl<const DIGITS: u8> NanoParse for Fixed<DIGITS> {
+    fn parse_bytes(s: &[u8]) -> Result<Self, ParseError> {
+        nano_fmt::parse_decimal(s, DIGITS).map(Self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use super::*;
+
+    fn to_vec<T: NanoDisplay>(value: T) -> Vec<u8> {
+        let mut buf = Vec::new();
+        value.fmt(&mut buf);
+        buf
+    }
+
+    #[test]
+    fn display() {
+        assert_eq!(to_vec(Fixed2::from_bits(0)), b"0.00");
+        assert_eq!(to_vec(Fixed2::from_bits(5)), b"0.05");
+        assert_eq!(to_vec(Fixed2::from_bits(3420)), b"34.20");
+        assert_eq!(to_vec(Fixed2::from_bits(-105)), b"-1.05");
+        assert_eq!(to_vec(Fixed2::MIN), b"-21474836.48");
+        assert_eq!(to_vec(Fixed::<3>::from_bits(1)), b"0.001");
+        assert_eq!(to_vec(Fixed::<0>::from_bits(42)), b"42");
+        assert_eq!(to_vec(Fixed::<9>::MAX), b"2.147483647");
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(Fixed2::parse_bytes(b"34.2"), Ok(Fixed2::from_bits(3420)));
+        assert_eq!(Fixed2::parse_bytes(b"-0.05"), Ok(Fixed2::from_bits(-5)));
+        assert_eq!(Fixed::<4>::parse_bytes(b"0.0057"), Ok(Fixed::from_bits(57)));
+        assert_eq!(Fixed2::parse_bytes(b"99999999"), Err(ParseError::Overflow));
+    }
+
+    #[test]
+    fn arithmetic() {
+        let a = Fixed2::from_int(3).unwrap();
+        let b = Fixed2::from_bits(150);
+
+        assert_eq!(a.checked_add(b), Some(Fixed2::from_bits(450)));
+        assert_eq!(a.checked_sub(b), Some(Fixed2::from_bits(150)));
+        assert_eq!(b.checked_sub(a), Some(Fixed2::from_bits(-150)));
+        assert_eq!(b.checked_mul_int(3), Some(Fixed2::from_bits(450)));
+        assert_eq!(Fixed2::from_int(i32::MAX), None);
+
+        assert_eq!(Fixed2::MAX.checked_add(b), None);
+        assert_eq!(Fixed2::MAX.saturating_add(b), Fixed2::MAX);
+        assert_eq!(Fixed2::MIN.checked_sub(b), None);
+        assert_eq!(Fixed2::MIN.saturating_sub(b), Fixed2::MIN);
+        assert_eq!(Fixed2::MAX.checked_mul_int(2), None);
+        assert_eq!(Fixed2::MAX.saturating_mul_int(-2), Fixed2::MIN);
+    }
+
+    #[test]
+    fn division() {
+        let a = Fixed2::from_bits(100);
+
+        assert_eq!(
+            a.checked_div_int(3, Rounding::TowardZero),
+            Some(Fixed2::from_bits(33))
+        );
+        assert_eq!(
+            a.checked_div_int(3, Rounding::Ceil),
+            Some(Fixed2::from_bits(34))
+        );
+        assert_eq!(
+            a.checked_div_int(-3, Rounding::Floor),
+            Some(Fixed2::from_bits(-34))
+        );
+        assert_eq!(
+            a.checked_div_int(-3, Rounding::Ceil),
+            Some(Fixed2::from_bits(-33))
+        );
+        assert_eq!(a.checked_div_int(0, Rounding::Nearest), None);
+        assert_eq!(Fixed2::MIN.checked_div_int(-1, Rounding::Nearest), None);
+    }
+
+    #[test]
+    fn rounding() {
+        let cases = [
+            // (n, d, toward zero, floor, ceil, nearest)
+            (7, 2, 3, 3, 4, 4),
+            (-7, 2, -3, -4, -3, -4),
+            (5, 3, 1, 1, 2, 2),
+            (-5, 3, -1, -2, -1, -2),
+            (4, 3, 1, 1, 2, 1),
+            (6, 3, 2, 2, 2, 2),
+            (i32::MAX, i32::MIN, 0, -1, 0, -1),
+        ];
+
+        for (n, d, zero, floor, ceil, nearest) in cases {
+            assert_eq!(div_round(n, d, Rounding::TowardZero), zero);
+            assert_eq!(div_round(n, d, Rounding::Floor), floor);
+            assert_eq!(div_round(n, d, Rounding::Ceil), ceil);
+            assert_eq!(div_round(n, d, Rounding::Nearest), nearest);
+        }
+    }
+
+    #[test]
+    fn rescale() {
+        let a = Fixed::<4>::from_bits(12_345);
+
+        assert_eq!(
+            a.rescale::<2>(Rounding::TowardZero),
+            Some(Fixed2::from_bits(123))
+        );
+        assert_eq!(
+            a.rescale::<2>(Rounding::Nearest),
+            Some(Fixed2::from_bits(123))
+        );
+        assert_eq!(
+            a.rescale::<3>(Rounding::Nearest),
+            Some(Fixed::from_bits(1235))
+        );
+        assert_eq!(
+            a.rescale::<6>(Rounding::Nearest),
+            Some(Fixed::from_bits(1_234_500))
+        );
+        assert_eq!(a.to_int(Rounding::Nearest), 1);
+
+        // Dropping all decimal digits of -2.50 rounds half away from zero.
+        let a = Fixed2::from_bits(-250);
+        assert_eq!(
+            a.rescale::<0>(Rounding::Nearest),
+            Some(Fixed::from_bits(-3))
+        );
+
+        // Adding decimal digits to the largest values overflows.
+        assert_eq!(Fixed2::MAX.rescale::<5>(Rounding::Nearest), None);
+        assert_eq!(
+            Fixed2::MAX.saturating_rescale::<5>(Rounding::Nearest),
+            Fixed::MAX
+        );
+        assert_eq!(
+            Fixed2::MIN.saturating_rescale::<5>(Rounding::Nearest),
+            Fixed::MIN
+        );
     }
 }
@@ -1,12 +1,34 @@
 #![no_std]
+#![cfg_attr(target_arch = "avr", feature(asm_experimental_arch))]
 
+pub mod alarm;
+#[cfg(target_arch = "avr")]
 pub mod beeper;
+#[cfg(target_arch = "avr")]
 pub mod clock;
+pub mod command;
+pub mod config;
+pub mod dead_time;
+pub mod debounce;
+#[cfg(target_arch = "avr")]
 pub mod delay;
+pub mod dose;
+#[cfg(target_arch = "avr")]
+pub mod eeprom;
 pub mod fixed;
+#[cfg(target_arch = "avr")]
 pub mod led;
+pub mod report;
 pub mod ring_buffer;
+pub mod settings;
+pub mod smoother;
+#[cfg(target_arch = "avr")]
 pub mod timer;
+pub mod tube;
+#[cfg(target_arch = "avr")]
 pub mod usart;
+#[cfg(target_arch = "avr")]
+pub mod watchdog;
 
+#[cfg(target_arch = "avr")]
 pub use attiny_hal as hal;
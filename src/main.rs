@@ -14,15 +14,27 @@ use core::{
     sync::atomic::{Ordering, compiler_fence},
 };
 use geiger::{
-    beeper::Beeper, delay::Delay, fixed::Fixed2, hal, led::Led, ring_buffer::RingBuffer,
-    timer::Timer, usart::Usart0,
+    alarm::{self, Alarm, Signal},
+    beeper::Beeper,
+    command::{self, Command, LineBuffer},
+    config::Config,
+    debounce::Debouncer,
+    delay::Delay,
+    dose::DoseAccumulator,
+    eeprom::Eeprom,
+    hal,
+    led::Led,
+    report::{Next, ReportStream, Values},
+    settings::{self, Settings},
+    smoother::{Report, Smoother},
+    timer::{self, Timer},
+    usart::Usart0,
+    watchdog::Watchdog,
 };
-use nano_fmt::NanoWrite;
 use panic_halt as _;
-use progmem::{P, write};
+use progmem::{P, writeln};
 
 use hal::{
-    pac::EXINT,
     port::Pin,
     port::{
         PD3, PD6, PinOps,
@@ -34,17 +46,20 @@ use hal::{
 /// UART baud rate.
 const BAUDRATE: u32 = 9600;
 
-/// Width of the PULSE output (in microseconds).
-const PULSE_WIDTH: u8 = 100;
+/// EEPROM address of the stored settings.
+const SETTINGS_ADDRESS: u8 = 0;
 
-const SHORT_PERIOD: usize = 5;
-const LONG_PERIOD: usize = 60;
+/// Minimum width of the PULSE output (in microseconds).
+const PULSE_WIDTH: u16 = 100;
 
-/// CPM threshold for fast averaging mode.
-const THRESHOLD: u16 = 1000;
+/// Width of the PULSE output in TIMER1 ticks.
+/// One tick is added because the first tick can be partial, so the pulse lasts
+/// between `PULSE_TICKS - 1` and `PULSE_TICKS` ticks (128us to 160us).
+const PULSE_TICKS: u16 = PULSE_WIDTH.div_ceil(timer::TICK_US) + 1;
 
-// CPM to uSv/hr conversion factor (x10,000 to avoid float).
-const SCALE_FACTOR: u32 = 57u32;
+/// Number of watchdog ticks the LED flash and the click on the piezo last.
+/// The first tick can be partial, so the click lasts 16ms to 32ms.
+const CLICK_TICKS: u8 = 2;
 
 /// Flags for events that can wakeup the main loop.
 struct EventFlags(Cell<u8>);
@@ -59,6 +74,14 @@ impl EventFlags {
     const GM_EVENT: u8 = 0x01;
     /// Flag that tells main loop when 1 second has passed.
     const TICK_EVENT: u8 = 0x02;
+    /// Flag that tells main loop when a command line was received.
+    const COMMAND_EVENT: u8 = 0x04;
+    /// Flag that tells main loop when the watchdog timer has ticked.
+    const WATCHDOG_EVENT: u8 = 0x08;
+    /// Flag that tells main loop when settings have to be saved.
+    const SETTINGS_EVENT: u8 = 0x10;
+    /// Flag that tells main loop when the output waits for a field.
+    const OUTPUT_EVENT: u8 = 0x20;
 
     /// Indicate that a GM event has occured.
     pub fn set_gm_event(&self) {
@@ -70,9 +93,35 @@ impl EventFlags {
         self.0.update(|f| f | Self::TICK_EVENT);
     }
 
-    /// Returns `true` if any of the events has occured.
-    pub fn has_any_event(&self) -> bool {
-        self.0.get() != 0
+    /// Indicate that a command line was received.
+    pub fn set_command_event(&self) {
+        self.0.update(|f| f | Self::COMMAND_EVENT);
+    }
+
+    /// Indicate that the watchdog timer has ticked.
+    pub fn set_watchdog_event(&self) {
+        self.0.update(|f| f | Self::WATCHDOG_EVENT);
+    }
+
+    /// Indicate that settings were changed.
+    pub fn set_settings_event(&self) {
+        self.0.update(|f| f | Self::SETTINGS_EVENT);
+    }
+
+    /// Indicate that the output waits for a field.
+    pub fn set_output_event(&self) {
+        self.0.update(|f| f | Self::OUTPUT_EVENT);
+    }
+
+    /// Returns `true` if any of the events can be handled.
+    /// Tick and command events wait while `output_idle` is `false`.
+    pub fn has_any_event(&self, output_idle: bool) -> bool {
+        let mask = if output_idle {
+            u8::MAX
+        } else {
+            !(Self::TICK_EVENT | Self::COMMAND_EVENT)
+        };
+        self.0.get() & mask != 0
     }
 
     /// Returns and resets GM event status.
@@ -88,6 +137,34 @@ impl EventFlags {
         self.0.set(val & !Self::TICK_EVENT);
         val & Self::TICK_EVENT != 0
     }
+
+    /// Returns and resets command event status.
+    pub fn take_command_event(&self) -> bool {
+        let val = self.0.get();
+        self.0.set(val & !Self::COMMAND_EVENT);
+        val & Self::COMMAND_EVENT != 0
+    }
+
+    /// Returns and resets watchdog event status.
+    pub fn take_watchdog_event(&self) -> bool {
+        let val = self.0.get();
+        self.0.set(val & !Self::WATCHDOG_EVENT);
+        val & Self::WATCHDOG_EVENT != 0
+    }
+
+    /// Returns and resets settings event status.
+    pub fn take_settings_event(&self) -> bool {
+        let val = self.0.get();
+        self.0.set(val & !Self::SETTINGS_EVENT);
+        val & Self::SETTINGS_EVENT != 0
+    }
+
+    /// Returns and resets output event status.
+    pub fn take_output_event(&self) -> bool {
+        let val = self.0.get();
+        self.0.set(val & !Self::OUTPUT_EVENT);
+        val & Self::OUTPUT_EVENT != 0
+    }
 }
 
 /// Data that is shared by multiple tasks.
@@ -98,8 +175,14 @@ struct SharedData {
     cps: Cell<u16>,
     /// Flag used to mute beeper.
     no_beep: Cell<bool>,
-    /// Flags for tick and GM events.
+    /// Flags for tick, GM, command, watchdog, settings and output events.
     event_flags: EventFlags,
+    /// Command line received over the serial port.
+    line: Cell<LineBuffer>,
+    /// Debouncer for the button.
+    button: Cell<Debouncer>,
+    /// Report or reply that is being transmitted by `USART_UDRE` interrupt handler.
+    output: Cell<ReportStream>,
 }
 
 impl SharedData {
@@ -109,31 +192,18 @@ impl SharedData {
             cps: Cell::new(0),
             no_beep: Cell::new(false),
             event_flags: EventFlags::new(),
+            line: Cell::new(LineBuffer::new()),
+            button: Cell::new(Debouncer::new()),
+            output: Cell::new(ReportStream::empty()),
         }
     }
 }
 
 static SHARED_DATA: Mutex<SharedData> = Mutex::new(SharedData::new());
 
-struct Smoother {
-    buffer: RingBuffer<LONG_PERIOD>,
-    /// GM counts per minute in slow mode.
-    slow_cpm: u16,
-}
-
-impl Smoother {
-    pub const fn new() -> Self {
-        Self {
-            buffer: RingBuffer::new(),
-            slow_cpm: 0,
-        }
-    }
-}
-
 // TODO: Find a way to get rid of configs
 static mut PULSE: MaybeUninit<Pin<Output, PD6>> = MaybeUninit::uninit();
 static mut BUTTON: MaybeUninit<Pin<Input<PullUp>, PD3>> = MaybeUninit::uninit();
-static mut SHARED_EXINT: MaybeUninit<EXINT> = MaybeUninit::uninit();
 
 /// Pin change interrupt for pin INT0
 /// This interrupt is called on the falling edge of a GM pulse.
@@ -149,36 +219,46 @@ fn INT0() {
     shared.event_flags.set_gm_event();
 
     // Send a pulse to the PULSE connector.
-    // A delay of 100us limits the max CPS to about 8000.
-    // You can comment out this code and increase the max CPS possible (up to 65535!).
-    // SAFETY: PULSE is initialized in the main function and is exclusively used here.
+    // The pulse is ended by TIMER1_COMPB interrupt, pulses that arrive while
+    // the output is high extend it.
+    // SAFETY: PULSE is initialized in the main function and is only used in interrupt handlers.
     let pulse = unsafe { PULSE.assume_init_mut() };
     pulse.set_high();
-    Delay::new().delay_us(PULSE_WIDTH);
+    // SAFETY: We are inside an interrupt handler and TIMER1 is initialized.
+    unsafe { Timer::schedule_compare_b(PULSE_TICKS) };
+}
+
+/// TIMER1 compare B interrupt.
+/// This interrupt is called at the end of the PULSE output.
+#[avr_device::interrupt(attiny2313)]
+fn TIMER1_COMPB() {
+    // SAFETY: PULSE is initialized in the main function and is only used in interrupt handlers.
+    let pulse = unsafe { PULSE.assume_init_mut() };
     pulse.set_low();
+    // SAFETY: We are inside an interrupt handler and TIMER1 is initialized.
+    unsafe { Timer::cancel_compare_b() };
 }
 
-/// Pin change interrupt for pin INT1 (pushbutton)
-/// If the user pushes the button, this interrupt is executed.
-/// We need to be careful about switch bounce, which will make the interrupt
-/// execute multiple times if we're not careful.
+/// Watchdog timer interrupt.
+/// This interrupt is called about every 16ms to sample the pushbutton.
+/// Switch bounce is filtered by requiring several consecutive equal samples.
 #[avr_device::interrupt(attiny2313)]
-fn INT1() {
+fn WDT_OVERFLOW() {
     // SAFETY: We are inside a blocking interrupt.
     let cs = unsafe { CriticalSection::new() };
 
-    Delay::new().delay_ms(25u8);
-
-    // Is button still pressed?
+    // SAFETY: BUTTON is initialized in the main function and is exclusively used here.
     let button = unsafe { BUTTON.assume_init_ref() };
-    if button.is_low() {
-        let shared = SHARED_DATA.borrow(cs);
+
+    let shared = SHARED_DATA.borrow(cs);
+    shared.event_flags.set_watchdog_event();
+
+    let mut debouncer = shared.button.get();
+    if debouncer.update(button.is_low()) {
         shared.no_beep.update(|flag| !flag);
+        shared.event_flags.set_settings_event();
     }
-
-    // Clear interrupt flag to avoid executing ISR again due to switch bounce
-    let exint = unsafe { SHARED_EXINT.assume_init_mut() };
-    exint.eifr.write(|w| w.intf().bits(0b10));
+    shared.button.set(debouncer);
 }
 
 /// TIMER1 compare interrupt.
@@ -195,78 +275,292 @@ fn TIMER1_COMPA() {
     shared.cps.set(cps);
 }
 
+/// USART receive complete interrupt.
+/// Collects received characters into a command line.
+#[avr_device::interrupt(attiny2313)]
+fn USART_RX() {
+    // SAFETY: We are inside a blocking interrupt.
+    let cs = unsafe { CriticalSection::new() };
+
+    // SAFETY: We are inside USART_RX interrupt handler.
+    let b = unsafe { Usart0::read_received() };
+
+    let shared = SHARED_DATA.borrow(cs);
+    let mut line = shared.line.get();
+    if line.push(b) {
+        // Tell main program loop that a command line is ready.
+        shared.event_flags.set_command_event();
+    }
+    shared.line.set(line);
+}
+
+/// USART data register empty interrupt.
+/// Transmits the periodic report or a reply to a command.
+/// Fields are formatted by the main loop, so this only copies bytes.
+#[avr_device::interrupt(attiny2313)]
+fn USART_UDRE() {
+    // SAFETY: We are inside a blocking interrupt.
+    let cs = unsafe { CriticalSection::new() };
+
+    let shared = SHARED_DATA.borrow(cs);
+    let mut output = shared.output.get();
+    let b = match output.next_byte() {
+        Next::Byte(b) => Some(b),
+        Next::Field => {
+            // Tell main program loop to format the field.
+            shared.event_flags.set_output_event();
+            None
+        }
+        Next::End => None,
+    };
+    shared.output.set(output);
+
+    // SAFETY: We are inside USART_UDRE interrupt handler.
+    unsafe { Usart0::transmit_next(b) };
+}
+
 /// Flash LED and beep the piezo.
-fn check_event<P: PinOps>(led: &mut Led<Pin<Output, P>>, beeper: &mut Beeper) {
+/// The click is ended by `signal_alarm` and is skipped while a beep pattern uses the outputs.
+fn check_event<P: PinOps>(
+    signal: &Signal,
+    click: &mut u8,
+    led: &mut Led<Pin<Output, P>>,
+    beeper: &mut Beeper,
+) {
     let (event_flag, no_beep) = interrupt::free(|cs| {
         let shared = SHARED_DATA.borrow(cs);
         let event_flag = shared.event_flags.take_gm_event();
         (event_flag, shared.no_beep.get())
     });
 
-    if event_flag {
+    if event_flag && !signal.is_active() {
         led.turn_on();
 
         if !no_beep {
             beeper.turn_on();
         }
 
-        // 10ms delay gives a nice short flash and 'click' on the piezo.
-        Delay::new().delay_ms(10u8);
+        *click = CLICK_TICKS;
+    }
+}
+
+/// Execute a command received over the serial port.
+/// The command waits until the previous output is transmitted, so it does not
+/// change values used by that output.
+fn check_command(
+    serial: &mut Usart0,
+    config: &mut Config,
+    smoother: &mut Smoother,
+    total: &mut DoseAccumulator,
+) {
+    let line = interrupt::free(|cs| {
+        let shared = SHARED_DATA.borrow(cs);
+
+        if !shared.output.get().is_idle() || !shared.event_flags.take_command_event() {
+            return None;
+        }
+
+        let mut buffer = shared.line.get();
+        let line = buffer.take();
+        // A discarded line can be waiting to be reported.
+        if buffer.is_ready() {
+            shared.event_flags.set_command_event();
+        }
+        shared.line.set(buffer);
+        Some(line)
+    });
+
+    let Some(line) = line else {
+        return;
+    };
+
+    let command = match line.line().and_then(Command::parse) {
+        Ok(command) => command,
+        Err(err) => {
+            let reply = match err {
+                command::Error::TooLong => P!("ERROR, TOO LONG"),
+                command::Error::Unknown => P!("ERROR, UNKNOWN COMMAND"),
+                command::Error::InvalidArgument => P!("ERROR, INVALID ARGUMENT"),
+                command::Error::Overrun => P!("ERROR, OVERRUN"),
+            };
+            start_output(serial, ReportStream::reply(reply));
+            return;
+        }
+    };
+
+    let mut reply = ReportStream::reply(P!("OK"));
+    match command {
+        Command::Mute => set_no_beep(true),
+        Command::Unmute => set_no_beep(false),
+        Command::Reset => *smoother = Smoother::new(),
+        Command::ResetTotal => total.reset(),
+        Command::GetConfig => reply = ReportStream::config(),
+        Command::SetThreshold(threshold) => config.threshold = threshold,
+        Command::SetTube(tube) => config.tube = tube,
+        Command::SetWarning(threshold) => config.warning_threshold = threshold,
+        Command::SetAlarm(threshold) => config.alarm_threshold = threshold,
+        Command::SetFormat(format) => config.format = format,
+    }
+
+    if command.changes_settings() {
+        interrupt::free(|cs| SHARED_DATA.borrow(cs).event_flags.set_settings_event());
+    }
+    start_output(serial, reply);
+}
+
+/// Set the beeper mute flag.
+fn set_no_beep(no_beep: bool) {
+    interrupt::free(|cs| SHARED_DATA.borrow(cs).no_beep.set(no_beep));
+}
+
+/// Load settings from EEPROM.
+/// Returns default settings if EEPROM does not contain valid settings.
+fn load_settings(eeprom: &mut Eeprom) -> Settings {
+    let mut bytes = [0; settings::SIZE];
+    eeprom.read(SETTINGS_ADDRESS, &mut bytes);
+    Settings::from_bytes(&bytes).unwrap_or(Settings::new())
+}
+
+/// Store settings in EEPROM after they were changed.
+/// Nothing is written if the stored settings are the same, including their CRC.
+fn save_settings(eeprom: &mut Eeprom, config: &Config) {
+    let no_beep = interrupt::free(|cs| {
+        let shared = SHARED_DATA.borrow(cs);
+        shared
+            .event_flags
+            .take_settings_event()
+            .then(|| shared.no_beep.get())
+    });
 
-        led.turn_off();
-        beeper.turn_off();
+    if let Some(no_beep) = no_beep {
+        let settings = Settings {
+            config: *config,
+            no_beep,
+        };
+        let bytes = settings.to_bytes();
+        if !eeprom.contains(SETTINGS_ADDRESS, &bytes) {
+            eeprom.write(SETTINGS_ADDRESS, &bytes);
+        }
+    }
+}
+
+/// Signal dose rate warning or alarm using the LED and the beeper.
+/// The beep pattern and the click are advanced on every watchdog tick.
+fn signal_alarm<P: PinOps>(
+    signal: &mut Signal,
+    click: &mut u8,
+    led: &mut Led<Pin<Output, P>>,
+    beeper: &mut Beeper,
+) {
+    let tick = interrupt::free(|cs| SHARED_DATA.borrow(cs).event_flags.take_watchdog_event());
+    if !tick {
+        return;
+    }
+
+    match signal.tick() {
+        Some(true) => {
+            *click = 0;
+            led.turn_on();
+            if signal.use_beeper() {
+                beeper.turn_on_alarm();
+            } else {
+                beeper.turn_off();
+            }
+        }
+        Some(false) => {
+            *click = 0;
+            led.turn_off();
+            beeper.turn_off();
+        }
+        None if *click > 0 => {
+            *click -= 1;
+            if *click == 0 {
+                led.turn_off();
+                beeper.turn_off();
+            }
+        }
+        None => {}
     }
 }
 
 /// Log data over the serial port.
-fn send_report<W>(w: &mut W, smoother: &mut Smoother)
-where
-    W: NanoWrite,
-{
-    let report = interrupt::free(|cs| {
+/// The report is transmitted in background by `USART_UDRE` interrupt handler.
+///
+/// There is no transmit buffer: a tick that occurs while the previous report or
+/// reply is transmitted waits until it ends, nothing is dropped or truncated.
+/// A report takes less than 0.1s, but if the output is still busy at the next
+/// tick, the counts of the waiting second are replaced and not reported.
+///
+/// Returns the report and the alarm level if a report was sent, fields of the
+/// report are filled from them.
+fn send_report(
+    serial: &mut Usart0,
+    config: &Config,
+    smoother: &mut Smoother,
+    alarm: &mut Alarm,
+    total: &mut DoseAccumulator,
+) -> Option<(Report, alarm::Level)> {
+    let cps = interrupt::free(|cs| {
         let shared = SHARED_DATA.borrow(cs);
 
-        if shared.event_flags.take_tick_event() {
+        if shared.output.get().is_idle() && shared.event_flags.take_tick_event() {
             Some(shared.cps.get())
         } else {
             None
         }
     });
 
-    if let Some(cps) = report {
-        write!(w, "CPS, {}, CPM, ", u32::from(cps));
-
-        let count = cps.try_into().unwrap_or(u8::MAX);
-        let oldest_count = smoother.buffer.put(count);
-
-        smoother.slow_cpm -= u16::from(oldest_count);
-        smoother.slow_cpm += u16::from(count);
+    cps.map(|cps| {
+        let report = smoother.update(cps, config);
+        let level = alarm.update(report.cpm, config.warning_threshold, config.alarm_threshold);
+        total.add(
+            report.cps,
+            report.corrected_cps,
+            config.tube.profile().scale_factor,
+        );
+        start_output(serial, ReportStream::report());
+
+        (report, level)
+    })
+}
 
-        let (cpm, mode_str) = {
-            if cps > u16::from(u8::MAX) {
-                (u32::from(cps) * 60, P!("INST"))
-            } else if smoother.slow_cpm <= THRESHOLD {
-                // Report cpm based on last 60 samples.
-                (u32::from(smoother.slow_cpm), P!("SLOW"))
-            } else {
-                // Report cpm based on last 5 samples.
-                let mut fast_cpm = 0u32;
-                for val in smoother.buffer.iter().take(SHORT_PERIOD) {
-                    fast_cpm += u32::from(val);
-                }
-                const FAST_CPM_SCALE: u32 = (LONG_PERIOD / SHORT_PERIOD) as u32;
-                fast_cpm *= FAST_CPM_SCALE;
-                (fast_cpm, P!("FAST"))
-            }
-        };
+/// Format the field the output waits for and resume the transmission.
+/// `report` and `level` are the ones returned by the last `send_report` call.
+/// Commands and ticks wait until the output is transmitted, so `config` and
+/// `total` do not change in the middle of a line either.
+fn fill_output(
+    serial: &mut Usart0,
+    config: &Config,
+    report: Report,
+    level: alarm::Level,
+    total: &DoseAccumulator,
+) {
+    let output = interrupt::free(|cs| {
+        let shared = SHARED_DATA.borrow(cs);
+        shared
+            .event_flags
+            .take_output_event()
+            .then(|| (shared.output.get(), shared.no_beep.get()))
+    });
 
-        write!(w, "{}, uSv/hr, ", cpm);
+    let Some((mut output, no_beep)) = output else {
+        return;
+    };
 
-        let usv_scaled = cpm * SCALE_FACTOR / 100;
-        let usv = Fixed2::from_bits(usv_scaled);
+    output.fill(&Values {
+        report,
+        level,
+        total,
+        config,
+        no_beep,
+    });
+    start_output(serial, output);
+}
 
-        write!(w, "{}, {}\r\n", usv, mode_str);
-    }
+/// Start background transmission of `output`.
+fn start_output(serial: &mut Usart0, output: ReportStream) {
+    interrupt::free(|cs| SHARED_DATA.borrow(cs).output.set(output));
+    serial.start_transmit();
 }
 
 /// Wait for an event to occur.
@@ -278,8 +572,11 @@ fn wait_for_event() {
     };
 
     let shared = SHARED_DATA.borrow(cs);
+    let output_idle = shared.output.get().is_idle();
 
-    if !shared.event_flags.has_any_event() {
+    // Events that wait for the output do not prevent sleeping, `USART_UDRE`
+    // interrupt at the end of the output wakes the main loop to handle them.
+    if !shared.event_flags.has_any_event(output_idle) {
         // Go to sleep until next interrupt.
         // This has to be inline assembly so that the compiler does not insert
         // additional instructions in between.
@@ -301,11 +598,41 @@ fn wait_for_event() {
 #[inline(always)]
 unsafe fn main() -> ! {
     static mut SMOOTHER: Smoother = Smoother::new();
+    static mut CONFIG: Config = Config::new();
+    static mut ALARM: Alarm = Alarm::new();
+    static mut SIGNAL: Signal = Signal::new();
+    static mut TOTAL: DoseAccumulator = DoseAccumulator::new();
 
     // SAFETY: This is the only place where we get the peripherals.
     let dp = unsafe { hal::Peripherals::steal() };
     let pins = hal::pins!(dp);
 
+    // Enable internal pull up resistor on pin connected to button.
+    let button = pins.pd3.into_pull_up_input();
+
+    // Restore settings, or reset them to defaults if the button is held at power-on.
+    // Give the pull up resistor some time to charge the pin.
+    Delay::new().delay_ms(10u8);
+    let factory_reset = button.is_low();
+
+    // Holding the button at power-on does not toggle mute, it has to be released first.
+    interrupt::free(|cs| {
+        SHARED_DATA
+            .borrow(cs)
+            .button
+            .set(Debouncer::with_state(factory_reset));
+    });
+
+    let mut eeprom = Eeprom::new(dp.EEPROM);
+    let settings = if factory_reset {
+        eeprom.write(SETTINGS_ADDRESS, &Settings::new().to_bytes());
+        Settings::new()
+    } else {
+        load_settings(&mut eeprom)
+    };
+    *CONFIG = settings.config;
+    set_no_beep(settings.no_beep);
+
     let mut serial = Usart0::new(
         dp.USART,
         pins.pd0.into_pull_up_input(),
@@ -313,10 +640,13 @@ unsafe fn main() -> ! {
         BAUDRATE,
     );
 
-    write!(
-        &mut serial,
-        "mightyohm.com Geiger Counter 1.00\r\nhttp://mightyohm.com/geiger\r\n"
-    );
+    writeln!(&mut serial, "mightyohm.com Geiger Counter 1.00");
+    writeln!(&mut serial, "http://mightyohm.com/geiger");
+    writeln!(&mut serial, "Tube: {}", CONFIG.tube.profile().name);
+
+    if factory_reset {
+        writeln!(&mut serial, "Settings reset to defaults");
+    }
 
     // Set pin connected to LED as outputs.
     let mut led = Led::new(pins.pb4.into_output());
@@ -324,20 +654,14 @@ unsafe fn main() -> ! {
     // Configure PULSE output.
     let pulse = pins.pd6.into_output();
 
-    // Enable internal pull up resistor on pin connected to button.
-    let button = pins.pd3.into_pull_up_input();
-
     // Set up external interrupts.
     // INT0 is triggered by a GM impulse.
-    // INT1 is triggered by pushing the button.
 
-    // Config interrupts on falling edge of INT0 and INT1.
-    dp.CPU
-        .mcucr
-        .modify(|_, w| w.isc0().falling().isc1().val_0x01());
+    // Config interrupt on falling edge of INT0.
+    dp.CPU.mcucr.modify(|_, w| w.isc0().falling());
 
-    // Enable external interrupts on pins INT0 and INT1.
-    dp.EXINT.gimsk.modify(|_, w| w.int().bits(0b11));
+    // Enable external interrupt on pin INT0.
+    dp.EXINT.gimsk.modify(|_, w| w.int().bits(0b01));
 
     // Configure the beeper connected to BP2 with timer TIMER0.
     let mut beeper = Beeper::new(pins.pb2.into_output(), dp.TC0);
@@ -345,13 +669,13 @@ unsafe fn main() -> ! {
     // Set up TIMER1 for 1 second interrupts.
     let _timer = Timer::new(dp.TC1);
 
-    let exint = dp.EXINT;
+    // Sample the button using watchdog timer interrupts.
+    let _watchdog = Watchdog::new(dp.WDT);
 
     // SAFETY: Shared peripherals are initialized exclusively in this function
     unsafe {
         PULSE.write(pulse);
         BUTTON.write(button);
-        SHARED_EXINT.write(exint);
     }
 
     // Set sleep mode to IDLE and enable sleep.
@@ -359,10 +683,24 @@ unsafe fn main() -> ! {
 
     compiler_fence(Ordering::SeqCst);
 
+    // Remaining watchdog ticks of the click.
+    let mut click = 0;
+    // Last report and alarm level, used to fill fields of the report.
+    let mut report = Report::new();
+    let mut level = alarm::Level::Normal;
+
     loop {
         wait_for_event();
 
-        check_event(&mut led, &mut beeper);
-        send_report(&mut serial, SMOOTHER);
+        check_event(SIGNAL, &mut click, &mut led, &mut beeper);
+        check_command(&mut serial, CONFIG, SMOOTHER, TOTAL);
+        save_settings(&mut eeprom, CONFIG);
+        if let Some(last) = send_report(&mut serial, CONFIG, SMOOTHER, ALARM, TOTAL) {
+            (report, level) = last;
+            let no_beep = interrupt::free(|cs| SHARED_DATA.borrow(cs).no_beep.get());
+            SIGNAL.start(level, no_beep);
+        }
+        fill_output(&mut serial, CONFIG, report, level, TOTAL);
+        signal_alarm(SIGNAL, &mut click, &mut led, &mut beeper);
     }
 }
@@ -0,0 +1,456 @@
+use core::mem;
+
+use nano_fmt::{ArrayWriter, NanoDisplay};
+use progmem::{LINE_END, P, PStr, ProgMem};
+
+use crate::{
+    alarm::Level,
+    config::{Config, Format},
+    dose::DoseAccumulator,
+    smoother::{Mode, Report},
+};
+
+/// Size of the field buffer, the longest field is a negative `Fixed2` value.
+const FIELD_SIZE: usize = 12;
+
+/// Part of a line.
+#[derive(Clone, Copy)]
+enum Piece {
+    /// Constant text.
+    Text(PStr),
+    /// Value that is filled by the main loop.
+    Field(Field),
+    /// End of the line.
+    End,
+}
+
+/// Values that are filled by the main loop.
+#[derive(Clone, Copy)]
+enum Field {
+    Cps,
+    Cpm,
+    DoseRate,
+    Mode,
+    Correction,
+    TotalCounts,
+    TotalDose,
+    Level,
+    Threshold,
+    Mute,
+    Tube,
+    Warning,
+    Alarm,
+    Format,
+}
+
+/// Index of the first piece of the periodic report.
+const REPORT: u8 = 0;
+/// Index of the line terminator of the periodic report.
+const REPORT_LINE_END: u8 = 16;
+/// Index of the first piece of the configuration reply.
+const CONFIG: u8 = 18;
+/// Index of the `OK` reply, [`ReportStream::reply`] replaces its text.
+const REPLY: u8 = 31;
+
+/// Pieces of all lines.
+#[cfg_attr(target_arch = "avr", unsafe(link_section = ".progmem.data"))]
+static PIECES: [ProgMem<Piece>; 34] = unsafe {
+    [
+        // Periodic report.
+        ProgMem::new(Piece::Text(P!("CPS, "))),
+        ProgMem::new(Piece::Field(Field::Cps)),
+        ProgMem::new(Piece::Text(P!(", CPM, "))),
+        ProgMem::new(Piece::Field(Field::Cpm)),
+        ProgMem::new(Piece::Text(P!(", uSv/hr, "))),
+        ProgMem::new(Piece::Field(Field::DoseRate)),
+        ProgMem::new(Piece::Text(P!(", "))),
+        ProgMem::new(Piece::Field(Field::Mode)),
+        // Fields that are not present in the original firmware.
+        ProgMem::new(Piece::Text(P!(", "))),
+        ProgMem::new(Piece::Field(Field::Correction)),
+        ProgMem::new(Piece::Text(P!(", TOTAL, "))),
+        ProgMem::new(Piece::Field(Field::TotalCounts)),
+        ProgMem::new(Piece::Text(P!(", uSv, "))),
+        ProgMem::new(Piece::Field(Field::TotalDose)),
+        ProgMem::new(Piece::Text(P!(", "))),
+        ProgMem::new(Piece::Field(Field::Level)),
+        ProgMem::new(Piece::Text(LINE_END)),
+        ProgMem::new(Piece::End),
+        // Configuration reply, followed by `OK`.
+        ProgMem::new(Piece::Text(P!("THRESHOLD, "))),
+        ProgMem::new(Piece::Field(Field::Threshold)),
+        ProgMem::new(Piece::Text(P!(", MUTE, "))),
+        ProgMem::new(Piece::Field(Field::Mute)),
+        ProgMem::new(Piece::Text(P!(", TUBE, "))),
+        ProgMem::new(Piece::Field(Field::Tube)),
+        ProgMem::new(Piece::Text(P!(", WARNING, "))),
+        ProgMem::new(Piece::Field(Field::Warning)),
+        ProgMem::new(Piece::Text(P!(", ALARM, "))),
+        ProgMem::new(Piece::Field(Field::Alarm)),
+        ProgMem::new(Piece::Text(P!(", FORMAT, "))),
+        ProgMem::new(Piece::Field(Field::Format)),
+        ProgMem::new(Piece::Text(LINE_END)),
+        // Reply to a command.
+        ProgMem::new(Piece::Text(P!("OK"))),
+        ProgMem::new(Piece::Text(LINE_END)),
+        ProgMem::new(Piece::End),
+    ]
+};
+
+/// Result of [`ReportStream::next_byte`].
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Next {
+    /// The next byte of the line.
+    Byte(u8),
+    /// The stream waits for [`ReportStream::fill`].
+    Field,
+    /// The whole line was produced.
+    End,
+}
+
+/// Values used to fill the fields of a line.
+pub struct Values<'a> {
+    /// Report for the last second.
+    pub report: Report,
+    /// Dose rate alarm level.
+    pub level: Level,
+    /// Total counts and accumulated dose.
+    pub total: &'a DoseAccumulator,
+    /// Current configuration.
+    pub config: &'a Config,
+    /// Flag used to mute beeper.
+    pub no_beep: bool,
+}
+
+/// Periodic report or a reply to a command that is produced one byte at a time.
+///
+/// This allows transmitting the line from an interrupt handler without
+/// storing its text. Constant text is read from program memory. When the
+/// stream reaches a field, it waits until the main loop formats the field
+/// into a small buffer using [`ReportStream::fill`], so producing a byte
+/// only copies it.
+#[derive(Clone, Copy)]
+pub struct ReportStream {
+    /// Index of the next piece.
+    piece: u8,
+    /// Rest of the current text.
+    text: PStr,
+    /// Rest of the formatted field, aligned to the end and padded with zeros.
+    field: [u8; FIELD_SIZE],
+}
+
+impl ReportStream {
+    /// Index of the piece when there is nothing to produce.
+    const IDLE: u8 = u8::MAX;
+
+    /// Create a stream that has nothing to produce.
+    #[must_use]
+    pub const fn empty() -> Self {
+        Self {
+            piece: Self::IDLE,
+            text: P!(""),
+            field: [0; FIELD_SIZE],
+        }
+    }
+
+    /// Create a stream that starts at `piece`.
+    const fn start(piece: u8) -> Self {
+        let mut stream = Self::empty();
+        stream.piece = piece;
+        stream
+    }
+
+    /// Create a stream that produces a report line.
+    #[must_use]
+    pub const fn report() -> Self {
+        Self::start(REPORT)
+    }
+
+    /// Create a stream that produces the configuration followed by `OK`.
+    #[must_use]
+    pub const fn config() -> Self {
+        Self::start(CONFIG)
+    }
+
+    /// Create a stream that produces a line with `text`.
+    #[must_use]
+    pub const fn reply(text: PStr) -> Self {
+        let mut stream = Self::start(REPLY + 1);
+        stream.text = text;
+        stream
+    }
+
+    /// Returns `true` if the stream has nothing to produce.
+    #[must_use]
+    pub fn is_idle(&self) -> bool {
+        self.piece == Self::IDLE
+    }
+
+    /// Returns the next byte of the line.
+    ///
+    /// When the stream reaches a field, [`Next::Field`] is returned until the field
+    /// is filled.
+    pub fn next_byte(&mut self) -> Next {
+        loop {
+            if let Some((b, text)) = self.text.split_first() {
+                self.text = text;
+                return Next::Byte(b.get());
+            }
+
+            if let Some(b) = self.field.iter_mut().find(|b| **b != 0) {
+                return Next::Byte(mem::take(b));
+            }
+
+            match self.load() {
+                Some(Piece::Text(text)) => {
+                    self.text = text;
+                    self.piece += 1;
+                }
+                Some(Piece::Field(_)) => return Next::Field,
+                Some(Piece::End) | None => {
+                    self.piece = Self::IDLE;
+                    return Next::End;
+                }
+            }
+        }
+    }
+
+    /// Returns the next piece.
+    fn load(&self) -> Option<Piece> {
+        PIECES.get(usize::from(self.piece)).map(ProgMem::load)
+    }
+
+    /// Format the field the stream waits for.
+    pub fn fill(&mut self, values: &Values) {
+        let Some(Piece::Field(field)) = self.load() else {
+            return;
+        };
+        self.piece += 1;
+
+        let report = &values.report;
+        let config = values.config;
+        match field {
+            Field::Cps => self.format(report.cps),
+            Field::Cpm => self.format(report.cpm),
+            Field::DoseRate => self.format(report.dose),
+            Field::Mode => {
+                self.text = match report.mode {
+                    Mode::Slow => P!("SLOW"),
+                    Mode::Fast => P!("FAST"),
+                    Mode::Warmup => P!("WARMUP"),
+                };
+                if config.format == Format::Classic {
+                    // Continue with the line terminator.
+                    self.piece = REPORT_LINE_END;
+                }
+            }
+            Field::Correction => {
+                self.text = if report.saturated {
+                    P!("SAT")
+                } else if report.is_corrected() {
+                    P!("CORR")
+                } else {
+                    P!("RAW")
+                };
+            }
+            Field::TotalCounts => self.format(values.total.counts()),
+            Field::TotalDose => self.format(values.total.dose()),
+            Field::Level => {
+                self.text = match values.level {
+                    Level::Normal => P!("OK"),
+                    Level::Warning => P!("WARN"),
+                    Level::Alarm => P!("ALARM"),
+                };
+            }
+            Field::Threshold => self.format(config.threshold),
+            Field::Mute => self.text = if values.no_beep { P!("ON") } else { P!("OFF") },
+            Field::Tube => self.text = config.tube.profile().name,
+            Field::Warning => self.format(config.warning_threshold),
+            Field::Alarm => self.format(config.alarm_threshold),
+            Field::Format => {
+                self.text = match config.format {
+                    Format::Full => P!("FULL"),
+                    Format::Classic => P!("CLASSIC"),
+                };
+            }
+        }
+    }
+
+    /// Format `value` into the field buffer.
+    fn format<T: NanoDisplay>(&mut self, value: T) {
+        let mut w = ArrayWriter::<FIELD_SIZE>::new();
+        value.fmt(&mut w);
+        debug_assert!(!w.overflowed());
+
+        let bytes = w.as_bytes();
+        self.field = [0; FIELD_SIZE];
+        self.field[FIELD_SIZE - bytes.len()..].copy_from_slice(bytes);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::{fixed::Fixed2, tube::Tube};
+
+    const REPORT: Report = Report {
+        cps: 12,
+        corrected_cps: 13,
+        cpm: 720,
+        dose: Fixed2::from_bits(410),
+        mode: Mode::Fast,
+        saturated: false,
+    };
+
+    fn config(format: Format) -> Config {
+        Config {
+            format,
+            ..Config::new()
+        }
+    }
+
+    /// Collect the line, filling fields as the main loop does.
+    fn collect(mut stream: ReportStream, report: Report, level: Level, config: &Config) -> Vec<u8> {
+        // 1000 counts are 0.095 uSv with SBM-20.
+        let mut total = DoseAccumulator::new();
+        total.add(1000, 1000, 57);
+        let values = Values {
+            report,
+            level,
+            total: &total,
+            config,
+            no_beep: true,
+        };
+
+        let mut line = Vec::new();
+        loop {
+            match stream.next_byte() {
+                Next::Byte(b) => line.push(b),
+                Next::Field => stream.fill(&values),
+                Next::End => break,
+            }
+        }
+        assert!(stream.is_idle());
+
+        let line_end: Vec<u8> = LINE_END.into_iter().map(|b| b.get()).collect();
+        assert!(line.ends_with(&line_end));
+        line.truncate(line.len() - line_end.len());
+        line
+    }
+
+    #[test]
+    fn full() {
+        let line = collect(
+            ReportStream::report(),
+            REPORT,
+            Level::Warning,
+            &config(Format::Full),
+        );
+        assert_eq!(
+            line,
+            b"CPS, 12, CPM, 720, uSv/hr, 4.10, FAST, CORR, TOTAL, 1000, uSv, 0.09, WARN"
+        );
+    }
+
+    #[test]
+    fn saturated() {
+        let report = Report {
+            saturated: true,
+            ..REPORT
+        };
+        let line = collect(
+            ReportStream::report(),
+            report,
+            Level::Alarm,
+            &config(Format::Full),
+        );
+        assert_eq!(
+            line,
+            b"CPS, 12, CPM, 720, uSv/hr, 4.10, FAST, SAT, TOTAL, 1000, uSv, 0.09, ALARM"
+        );
+    }
+
+    #[test]
+    fn classic() {
+        let line = collect(
+            ReportStream::report(),
+            REPORT,
+            Level::Warning,
+            &config(Format::Classic),
+        );
+        assert_eq!(line, b"CPS, 12, CPM, 720, uSv/hr, 4.10, FAST");
+    }
+
+    #[test]
+    fn longest_fields() {
+        let report = Report {
+            cps: u16::MAX,
+            corrected_cps: u16::MAX,
+            cpm: u32::MAX,
+            dose: Fixed2::MIN,
+            mode: Mode::Warmup,
+            saturated: true,
+        };
+        let line = collect(
+            ReportStream::report(),
+            report,
+            Level::Normal,
+            &config(Format::Classic),
+        );
+        assert_eq!(
+            line,
+            b"CPS, 65535, CPM, 4294967295, uSv/hr, -21474836.48, WARMUP"
+        );
+    }
+
+    #[test]
+    fn config_reply() {
+        let config = Config {
+            threshold: 800,
+            tube: Tube::J305,
+            warning_threshold: 0,
+            alarm_threshold: 65535,
+            format: Format::Classic,
+        };
+        let line = collect(ReportStream::config(), REPORT, Level::Normal, &config);
+        let line_end: Vec<u8> = LINE_END.into_iter().map(|b| b.get()).collect();
+
+        let mut expected =
+            b"THRESHOLD, 800, MUTE, ON, TUBE, J305, WARNING, 0, ALARM, 65535, FORMAT, CLASSIC"
+                .to_vec();
+        expected.extend(&line_end);
+        expected.extend(b"OK");
+        assert_eq!(line, expected);
+    }
+
+    #[test]
+    fn reply() {
+        let line = collect(
+            ReportStream::reply(P!("ERROR, OVERRUN")),
+            REPORT,
+            Level::Normal,
+            &Config::new(),
+        );
+        assert_eq!(line, b"ERROR, OVERRUN");
+    }
+
+    #[test]
+    fn empty() {
+        let mut stream = ReportStream::empty();
+        assert!(stream.is_idle());
+        assert_eq!(stream.next_byte(), Next::End);
+
+        // Only the fields that are reached wait for the main loop.
+        let mut stream = ReportStream::report();
+        assert!(!stream.is_idle());
+        for &b in b"CPS, " {
+            assert_eq!(stream.next_byte(), Next::Byte(b));
+        }
+        assert_eq!(stream.next_byte(), Next::Field);
+        assert_eq!(stream.next_byte(), Next::Field);
+    }
+}
@@ -1,24 +1,52 @@
 use core::mem;
 
+/// Value that can be stored in a ring buffer.
+pub trait Sample: Copy + Into<u32> {
+    /// Value used to fill a new buffer.
+    const ZERO: Self;
+}
+
+impl Sample for u8 {
+    const ZERO: Self = 0;
+}
+
+impl Sample for u16 {
+    const ZERO: Self = 0;
+}
+
+impl Sample for u32 {
+    const ZERO: Self = 0;
+}
+
 /// Fixed size ring buffer.
-pub struct RingBuffer<const SIZE: usize> {
-    samples: [u8; SIZE],
+///
+/// `SIZE` should be between 1 and 255.
+pub struct RingBuffer<T, const SIZE: usize> {
+    samples: [T; SIZE],
+    /// Position of the next value.
     index: u8,
+    /// Number of values put into the buffer, at most `SIZE`.
+    len: u8,
 }
 
-impl<const SIZE: usize> RingBuffer<SIZE> {
-    /// Create a new buffer filled with zeroes.
+impl<T: Sample, const SIZE: usize> RingBuffer<T, SIZE> {
+    /// Create a new empty buffer.
     #[allow(clippy::new_without_default)]
     #[must_use]
     pub const fn new() -> Self {
+        const { assert!(SIZE > 0 && SIZE <= u8::MAX as usize) };
+
         Self {
-            samples: [0; SIZE],
+            samples: [T::ZERO; SIZE],
             index: 0,
+            len: 0,
         }
     }
 
-    /// Put a new value into the buffer returning the discarded value.
-    pub fn put(&mut self, value: u8) -> u8 {
+    /// Put a new value into the buffer.
+    ///
+    /// Returns the discarded value if the buffer was full.
+    pub fn put(&mut self, value: T) -> Option<T> {
         debug_assert!((self.index as usize) < self.samples.len());
         // SAFETY: `self.index` is always in bounds of `self.samples`.
         let elem = unsafe { self.samples.get_unchecked_mut(self.index as usize) };
@@ -29,37 +57,323 @@ impl<const SIZE: usize> RingBuffer<SIZE> {
         } else {
             self.index + 1
         };
-        old_value
+
+        if self.is_full() {
+            Some(old_value)
+        } else {
+            self.len += 1;
+            None
+        }
     }
 
-    /// Returns iterator over values in the buffer.
+    /// Replace the newest value in the buffer.
+    ///
+    /// Returns the replaced value, or `None` if the buffer is empty and nothing
+    /// was replaced.
+    pub fn replace_newest(&mut self, value: T) -> Option<T> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let i = if self.index == 0 {
+            SIZE - 1
+        } else {
+            self.index as usize - 1
+        };
+        debug_assert!(i < self.samples.len());
+        // SAFETY: `i` is always in bounds of `self.samples`.
+        let elem = unsafe { self.samples.get_unchecked_mut(i) };
+        Some(mem::replace(elem, value))
+    }
+
+    /// Returns the number of values in the buffer.
     #[must_use]
-    pub fn iter(&self) -> Iter<SIZE> {
+    pub fn len(&self) -> usize {
+        self.len as usize
+    }
+
+    /// Returns `true` if nothing was put into the buffer.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns `true` if the buffer holds `SIZE` values.
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.len as usize == SIZE
+    }
+
+    /// Remove all values from the buffer.
+    pub fn clear(&mut self) {
+        self.index = 0;
+        self.len = 0;
+    }
+
+    /// Returns iterator over values in the buffer, from the newest to the oldest.
+    #[must_use]
+    pub fn iter(&self) -> Iter<'_, T, SIZE> {
+        self.last_n(SIZE)
+    }
+
+    /// Returns iterator over at most `n` newest values, from the newest to the oldest.
+    #[must_use]
+    pub fn last_n(&self, n: usize) -> Iter<'_, T, SIZE> {
         Iter {
             samples: &self.samples,
             index: self.index,
+            front: 0,
+            back: n.min(self.len()) as u8,
+        }
+    }
+}
+
+/// Ring buffer that keeps the sum of all values.
+pub struct SummingRingBuffer<T, const SIZE: usize> {
+    buffer: RingBuffer<T, SIZE>,
+    /// Sum of all values in `buffer`.
+    ///
+    /// Wrapping arithmetic is used, so the sum is correct whenever it fits into `u32`.
+    sum: u32,
+}
+
+impl<T: Sample, const SIZE: usize> SummingRingBuffer<T, SIZE> {
+    /// Create a new empty buffer.
+    #[allow(clippy::new_without_default)]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            buffer: RingBuffer::new(),
+            sum: 0,
         }
     }
+
+    /// Put a new value into the buffer.
+    ///
+    /// Returns the discarded value if the buffer was full.
+    pub fn put(&mut self, value: T) -> Option<T> {
+        let old_value = self.buffer.put(value);
+        if let Some(old_value) = old_value {
+            self.sum = self.sum.wrapping_sub(old_value.into());
+        }
+        self.sum = self.sum.wrapping_add(value.into());
+        old_value
+    }
+
+    /// Replace the newest value in the buffer.
+    ///
+    /// Returns the replaced value, or `None` if the buffer is empty and nothing
+    /// was replaced.
+    pub fn replace_newest(&mut self, value: T) -> Option<T> {
+        let old_value = self.buffer.replace_newest(value)?;
+        self.sum = self
+            .sum
+            .wrapping_sub(old_value.into())
+            .wrapping_add(value.into());
+        Some(old_value)
+    }
+
+    /// Returns the sum of all values in the buffer.
+    #[must_use]
+    pub fn sum(&self) -> u32 {
+        self.sum
+    }
+
+    /// Remove all values from the buffer.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.sum = 0;
+    }
+
+    /// Returns the buffer that holds the values.
+    #[must_use]
+    pub fn buffer(&self) -> &RingBuffer<T, SIZE> {
+        &self.buffer
+    }
 }
 
 /// Iterator over ring buffer data.
-pub struct Iter<'a, const SIZE: usize> {
-    samples: &'a [u8; SIZE],
+pub struct Iter<'a, T, const SIZE: usize> {
+    samples: &'a [T; SIZE],
+    /// Position after the newest value.
     index: u8,
+    /// Age of the next value returned from the front, 0 is the newest value.
+    front: u8,
+    /// Age of the value after the last one returned from the back.
+    back: u8,
+}
+
+impl<T: Copy, const SIZE: usize> Iter<'_, T, SIZE> {
+    /// Returns a value by its age.
+    fn get(&self, age: u8) -> T {
+        // `age` is less than `SIZE`, so a single subtraction is enough to wrap around.
+        let mut i = self.index as usize + SIZE - 1 - age as usize;
+        if i >= SIZE {
+            i -= SIZE;
+        }
+        debug_assert!(i < self.samples.len());
+        // SAFETY: `i` is always in bounds of `self.samples`.
+        unsafe { *self.samples.get_unchecked(i) }
+    }
 }
 
-impl<const SIZE: usize> Iterator for Iter<'_, SIZE> {
-    type Item = u8;
+impl<T: Copy, const SIZE: usize> Iterator for Iter<'_, T, SIZE> {
+    type Item = T;
 
     fn next(&mut self) -> Option<Self::Item> {
-        self.index = if self.index == 0 {
-            (SIZE - 1) as u8
-        } else {
-            self.index - 1
-        };
-        debug_assert!((self.index as usize) < self.samples.len());
-        // SAFETY: `self.index` is always in bounds of `self.samples`.
-        let elem = unsafe { self.samples.get_unchecked(self.index as usize) };
-        Some(*elem)
+        if self.front == self.back {
+            return None;
+        }
+        let value = self.get(self.front);
+        self.front += 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = (self.back - self.front) as usize;
+        (len, Some(len))
+    }
+}
+
+impl<T: Copy, const SIZE: usize> DoubleEndedIterator for Iter<'_, T, SIZE> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.front == self.back {
+            return None;
+        }
+        self.back -= 1;
+        Some(self.get(self.back))
+    }
+}
+
+impl<T: Copy, const SIZE: usize> ExactSizeIterator for Iter<'_, T, SIZE> {}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use super::*;
+
+    #[test]
+    fn put() {
+        let mut buffer = RingBuffer::<u16, 3>::new();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.put(1000), None);
+        assert_eq!(buffer.put(2), None);
+        assert!(!buffer.is_full());
+        assert_eq!(buffer.put(3), None);
+        assert!(buffer.is_full());
+        assert_eq!(buffer.put(4), Some(1000));
+        assert_eq!(buffer.len(), 3);
+
+        let values: Vec<_> = buffer.iter().collect();
+        assert_eq!(values, [4, 3, 2]);
+    }
+
+    #[test]
+    fn iter() {
+        let mut buffer = RingBuffer::<u8, 4>::new();
+        assert_eq!(buffer.iter().next(), None);
+
+        buffer.put(1);
+        buffer.put(2);
+        let values: Vec<_> = buffer.iter().collect();
+        assert_eq!(values, [2, 1]);
+
+        for value in 3..=6 {
+            buffer.put(value);
+        }
+        let iter = buffer.iter();
+        assert_eq!(iter.len(), 4);
+        let values: Vec<_> = iter.collect();
+        assert_eq!(values, [6, 5, 4, 3]);
+
+        let values: Vec<_> = buffer.iter().rev().collect();
+        assert_eq!(values, [3, 4, 5, 6]);
+
+        let mut iter = buffer.iter();
+        assert_eq!(iter.next(), Some(6));
+        assert_eq!(iter.next_back(), Some(3));
+        assert_eq!(iter.len(), 2);
+        assert_eq!(iter.next_back(), Some(4));
+        assert_eq!(iter.next(), Some(5));
+        assert_eq!(iter.next(), None);
+        assert_eq!(iter.next_back(), None);
+    }
+
+    #[test]
+    fn last_n() {
+        let mut buffer = RingBuffer::<u8, 4>::new();
+        buffer.put(1);
+        buffer.put(2);
+        buffer.put(3);
+
+        let values: Vec<_> = buffer.last_n(2).collect();
+        assert_eq!(values, [3, 2]);
+        assert_eq!(buffer.last_n(10).len(), 3);
+        assert_eq!(buffer.last_n(0).next(), None);
+    }
+
+    #[test]
+    fn replace_newest() {
+        let mut buffer = SummingRingBuffer::<u16, 2>::new();
+        assert_eq!(buffer.replace_newest(1), None);
+        assert!(buffer.buffer().is_empty());
+
+        buffer.put(1);
+        assert_eq!(buffer.replace_newest(5), Some(1));
+        assert_eq!(buffer.sum(), 5);
+
+        buffer.put(2);
+        buffer.put(3);
+        assert_eq!(buffer.replace_newest(7), Some(3));
+        assert_eq!(buffer.sum(), 9);
+
+        let values: Vec<_> = buffer.buffer().iter().collect();
+        assert_eq!(values, [7, 2]);
+    }
+
+    #[test]
+    fn clear() {
+        let mut buffer = SummingRingBuffer::<u8, 2>::new();
+        buffer.put(1);
+        buffer.put(2);
+        buffer.put(3);
+
+        buffer.clear();
+        assert!(buffer.buffer().is_empty());
+        assert_eq!(buffer.sum(), 0);
+        assert_eq!(buffer.buffer().iter().next(), None);
+
+        assert_eq!(buffer.put(4), None);
+        assert_eq!(buffer.sum(), 4);
+        assert_eq!(buffer.buffer().len(), 1);
+    }
+
+    #[test]
+    fn sum() {
+        let mut buffer = SummingRingBuffer::<u16, 3>::new();
+        assert_eq!(buffer.sum(), 0);
+
+        buffer.put(u16::MAX);
+        buffer.put(u16::MAX);
+        buffer.put(1);
+        assert_eq!(buffer.sum(), 2 * u32::from(u16::MAX) + 1);
+
+        assert_eq!(buffer.put(2), Some(u16::MAX));
+        assert_eq!(buffer.sum(), u32::from(u16::MAX) + 3);
+    }
+
+    #[test]
+    fn wrapping_sum() {
+        let mut buffer = SummingRingBuffer::<u32, 2>::new();
+
+        // The intermediate sum overflows, but the final one fits.
+        buffer.put(u32::MAX);
+        buffer.put(u32::MAX);
+        buffer.put(1);
+        buffer.put(2);
+        assert_eq!(buffer.sum(), 3);
     }
 }
@@ -0,0 +1,156 @@
+use crate::{
+    config::{Config, Format},
+    tube::Tube,
+};
+
+/// Version of the settings layout, should be changed when the layout changes.
+const VERSION: u8 = 1;
+
+/// Size of serialized settings in bytes.
+pub const SIZE: usize = 11;
+
+/// Flag indicating that the beeper is muted.
+const FLAG_NO_BEEP: u8 = 0x01;
+
+/// User settings that are preserved across power cycles.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Settings {
+    /// Runtime configuration.
+    pub config: Config,
+    /// Flag used to mute beeper.
+    pub no_beep: bool,
+}
+
+impl Settings {
+    /// Create settings with default values.
+    #[allow(clippy::new_without_default)]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            config: Config::new(),
+            no_beep: false,
+        }
+    }
+
+    /// Serialize settings.
+    ///
+    /// The layout is: version, flags, threshold, tube, warning threshold,
+    /// alarm threshold, format, CRC-8. 16-bit values are little endian.
+    #[must_use]
+    pub fn to_bytes(&self) -> [u8; SIZE] {
+        let config = &self.config;
+        let threshold = config.threshold.to_le_bytes();
+        let warning_threshold = config.warning_threshold.to_le_bytes();
+        let alarm_threshold = config.alarm_threshold.to_le_bytes();
+        let flags = if self.no_beep { FLAG_NO_BEEP } else { 0 };
+
+        let mut bytes = [
+            VERSION,
+            flags,
+            threshold[0],
+            threshold[1],
+            config.tube as u8,
+            warning_threshold[0],
+            warning_threshold[1],
+            alarm_threshold[0],
+            alarm_threshold[1],
+            config.format as u8,
+            0,
+        ];
+        bytes[SIZE - 1] = crc8(&bytes[..SIZE - 1]);
+        bytes
+    }
+
+    /// Deserialize settings.
+    ///
+    /// Returns `None` if the data is corrupted or was written using a different layout version.
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8; SIZE]) -> Option<Self> {
+        if bytes[0] != VERSION || crc8(&bytes[..SIZE - 1]) != bytes[SIZE - 1] {
+            return None;
+        }
+
+        Some(Self {
+            config: Config {
+                threshold: u16::from_le_bytes([bytes[2], bytes[3]]),
+                tube: Tube::from_u8(bytes[4])?,
+                warning_threshold: u16::from_le_bytes([bytes[5], bytes[6]]),
+                alarm_threshold: u16::from_le_bytes([bytes[7], bytes[8]]),
+                format: Format::from_u8(bytes[9])?,
+            },
+            no_beep: bytes[1] & FLAG_NO_BEEP != 0,
+        })
+    }
+}
+
+/// Calculate CRC-8 with polynomial 0x07.
+fn crc8(data: &[u8]) -> u8 {
+    let mut crc = 0u8;
+
+    for &b in data {
+        crc ^= b;
+        for _ in 0..8 {
+            crc = if crc & 0x80 != 0 {
+                (crc << 1) ^ 0x07
+            } else {
+                crc << 1
+            };
+        }
+    }
+
+    crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn crc() {
+        assert_eq!(crc8(b""), 0);
+        assert_eq!(crc8(b"123456789"), 0xF4);
+    }
+
+    #[test]
+    fn round_trip() {
+        let settings = Settings::new();
+        assert_eq!(Settings::from_bytes(&settings.to_bytes()), Some(settings));
+
+        let settings = Settings {
+            config: Config {
+                threshold: 800,
+                tube: Tube::M4011,
+                warning_threshold: 0,
+                alarm_threshold: 65535,
+                format: Format::Classic,
+            },
+            no_beep: true,
+        };
+        assert_eq!(Settings::from_bytes(&settings.to_bytes()), Some(settings));
+    }
+
+    #[test]
+    fn erased() {
+        assert_eq!(Settings::from_bytes(&[0xFF; SIZE]), None);
+        assert_eq!(Settings::from_bytes(&[0; SIZE]), None);
+    }
+
+    #[test]
+    fn corrupted() {
+        let bytes = Settings::new().to_bytes();
+
+        for i in 0..SIZE {
+            let mut corrupted = bytes;
+            corrupted[i] ^= 0x10;
+            assert_eq!(Settings::from_bytes(&corrupted), None);
+        }
+    }
+
+    #[test]
+    fn invalid_values() {
+        let mut bytes = Settings::new().to_bytes();
+        bytes[4] = Tube::ALL.len() as u8;
+        bytes[SIZE - 1] = crc8(&bytes[..SIZE - 1]);
+        assert_eq!(Settings::from_bytes(&bytes), None);
+    }
+}
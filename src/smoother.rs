@@ -0,0 +1,406 @@
+use crate::{
+    config::Config,
+    dead_time,
+    fixed::{Fixed, Fixed2, Rounding},
+    ring_buffer::SummingRingBuffer,
+};
+
+/// Number of seconds in a bucket, also the shortest period used in FAST averaging mode.
+pub const SHORT_PERIOD: usize = 5;
+/// Number of seconds used in SLOW averaging mode.
+pub const LONG_PERIOD: usize = 60;
+/// Number of buckets that hold counts for `LONG_PERIOD` seconds.
+const BUCKETS: usize = LONG_PERIOD / SHORT_PERIOD;
+
+/// Averaging mode used to calculate CPM.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Mode {
+    /// CPM is based on the last 56 to 60 seconds.
+    Slow,
+    /// CPM is based on the last 5 to 9 seconds.
+    Fast,
+    /// Not enough samples were collected since reset, CPM is scaled from
+    /// the available samples and is less accurate.
+    Warmup,
+}
+
+/// Measurement report produced once a second.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Report {
+    /// GM counts during the last second.
+    pub cps: u16,
+    /// GM counts during the last second, corrected for the tube dead time.
+    pub corrected_cps: u16,
+    /// GM counts per minute, corrected for the tube dead time.
+    pub cpm: u32,
+    /// Equivalent dose rate in uSv/hr.
+    pub dose: Fixed2,
+    /// Averaging mode used to calculate `cpm`.
+    pub mode: Mode,
+    /// CPS is above the recommended maximum of the tube, so dead time
+    /// correction is not reliable.
+    pub saturated: bool,
+}
+
+impl Report {
+    /// Create a report without counts, used until the first report is produced.
+    #[allow(clippy::new_without_default)]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            cps: 0,
+            corrected_cps: 0,
+            cpm: 0,
+            dose: Fixed2::from_bits(0),
+            mode: Mode::Warmup,
+            saturated: false,
+        }
+    }
+
+    /// Returns `true` if dead time correction changed the count rate.
+    #[must_use]
+    pub fn is_corrected(&self) -> bool {
+        self.corrected_cps != self.cps
+    }
+}
+
+/// Running average of GM counts.
+///
+/// To save RAM, counts are summed into buckets of `SHORT_PERIOD` seconds. The
+/// newest bucket is filled second by second and is used together with the
+/// older ones, so the averaging periods are up to one bucket shorter.
+pub struct Smoother {
+    /// Corrected GM counts for the last `BUCKETS` buckets, saturating on overflow.
+    buckets: SummingRingBuffer<u16, BUCKETS>,
+    /// Number of seconds in the newest bucket.
+    seconds: u8,
+}
+
+impl Smoother {
+    /// Create a new instance without samples.
+    #[allow(clippy::new_without_default)]
+    #[must_use]
+    pub const fn new() -> Self {
+        Self {
+            buckets: SummingRingBuffer::new(),
+            seconds: 0,
+        }
+    }
+
+    /// Add a new sample and calculate a report.
+    ///
+    /// `cps` is the number of counts during the last second.
+    pub fn update(&mut self, cps: u16, config: &Config) -> Report {
+        let profile = config.tube.profile();
+        let corrected_cps = dead_time::correct(cps, profile.dead_time);
+
+        match self.buckets.buffer().iter().next() {
+            Some(newest) if usize::from(self.seconds) < SHORT_PERIOD => {
+                self.buckets
+                    .replace_newest(newest.saturating_add(corrected_cps));
+                self.seconds += 1;
+            }
+            _ => {
+                self.buckets.put(corrected_cps);
+                self.seconds = 1;
+            }
+        }
+
+        let (cpm, mode) = self.cpm(config.threshold);
+
+        Report {
+            cps,
+            corrected_cps,
+            cpm,
+            dose: dose_rate(cpm, profile.scale_factor),
+            mode,
+            saturated: cps > profile.max_cps,
+        }
+    }
+
+    /// Calculate CPM and averaging mode, the buffer should not be empty.
+    fn cpm(&self, threshold: u16) -> (u32, Mode) {
+        let buckets = self.buckets.buffer();
+        let slow_cpm = per_minute(self.buckets.sum(), self.seconds(buckets.len()));
+        if slow_cpm <= u32::from(threshold) {
+            // Report cpm based on the last minute.
+            let mode = if buckets.is_full() {
+                Mode::Slow
+            } else {
+                Mode::Warmup
+            };
+            (slow_cpm, mode)
+        } else {
+            // Report cpm based on the newest bucket, completed with the
+            // previous one while it is being filled.
+            let n = if usize::from(self.seconds) < SHORT_PERIOD {
+                2
+            } else {
+                1
+            };
+            let fast = buckets.last_n(n);
+            let samples = self.seconds(fast.len());
+            let fast_cpm = per_minute(fast.map(u32::from).sum(), samples);
+            let mode = if samples < SHORT_PERIOD {
+                Mode::Warmup
+            } else {
+                Mode::Fast
+            };
+            (fast_cpm, mode)
+        }
+    }
+
+    /// Returns the number of seconds in the newest `buckets` buckets.
+    fn seconds(&self, buckets: usize) -> usize {
+        (buckets - 1) * SHORT_PERIOD + usize::from(self.seconds)
+    }
+}
+
+/// Scale sum of counts during `samples` seconds to counts per minute.
+fn per_minute(sum: u32, samples: usize) -> u32 {
+    if samples == LONG_PERIOD {
+        sum
+    } else {
+        // The sum is at most `u16::MAX * BUCKETS`, so this cannot overflow.
+        sum * LONG_PERIOD as u32 / samples as u32
+    }
+}
+
+/// Convert CPM to equivalent dose rate in uSv/hr.
+///
+/// `scale_factor` is [`TubeProfile::scale_factor`](crate::tube::TubeProfile::scale_factor) of the tube.
+#[must_use]
+pub fn dose_rate(cpm: u32, scale_factor: u16) -> Fixed2 {
+    let bits = cpm.saturating_mul(u32::from(scale_factor));
+    let rate = Fixed::<4>::from_bits(i32::try_from(bits).unwrap_or(i32::MAX));
+    rate.saturating_rescale(Rounding::TowardZero)
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use super::*;
+    use crate::tube::Tube;
+
+    fn config(threshold: u16) -> Config {
+        Config {
+            threshold,
+            tube: Tube::Sbm20,
+            ..Config::new()
+        }
+    }
+
+    /// Create a smoother with all samples set to `cps`.
+    fn warmed_up(cps: u16) -> Smoother {
+        let mut smoother = Smoother::new();
+        for _ in 0..LONG_PERIOD {
+            smoother.update(cps, &config(u16::MAX));
+        }
+        smoother
+    }
+
+    #[test]
+    fn slow_mode() {
+        let mut smoother = warmed_up(0);
+
+        // The newest bucket holds one second, so CPM is scaled from 56 seconds.
+        let report = smoother.update(14, &config(1000));
+        assert_eq!(report.cps, 14);
+        assert_eq!(report.cpm, 14 * 60 / 56);
+        assert_eq!(report.mode, Mode::Slow);
+
+        let report = smoother.update(14, &config(1000));
+        assert_eq!(report.cpm, 28 * 60 / 57);
+
+        let mut report = report;
+        for _ in 2..SHORT_PERIOD {
+            report = smoother.update(0, &config(1000));
+        }
+        assert_eq!(report.cpm, 28);
+        assert_eq!(report.mode, Mode::Slow);
+    }
+
+    #[test]
+    fn warmup() {
+        let mut smoother = Smoother::new();
+
+        let report = smoother.update(10, &config(1000));
+        assert_eq!(report.cpm, 10 * 60);
+        assert_eq!(report.mode, Mode::Warmup);
+
+        let report = smoother.update(5, &config(1000));
+        assert_eq!(report.cpm, 15 * 30);
+        assert_eq!(report.mode, Mode::Warmup);
+
+        for _ in 2..LONG_PERIOD - 1 {
+            smoother.update(10, &config(1000));
+        }
+        let report = smoother.update(10, &config(1000));
+        assert_eq!(report.cpm, 595);
+        assert_eq!(report.mode, Mode::Slow);
+
+        // Reset restarts the warm-up.
+        let mut smoother = Smoother::new();
+        let report = smoother.update(1, &config(1000));
+        assert_eq!(report.cpm, 60);
+        assert_eq!(report.mode, Mode::Warmup);
+    }
+
+    #[test]
+    fn old_samples_are_discarded() {
+        let mut smoother = Smoother::new();
+
+        smoother.update(5, &config(1000));
+        for _ in 1..LONG_PERIOD {
+            smoother.update(1, &config(1000));
+        }
+
+        let report = smoother.update(1, &config(1000));
+        assert_eq!(report.cpm, LONG_PERIOD as u32);
+        assert_eq!(report.mode, Mode::Slow);
+    }
+
+    #[test]
+    fn fast_mode() {
+        let mut smoother = warmed_up(0);
+
+        let mut report = smoother.update(30, &config(100));
+        for _ in 1..SHORT_PERIOD {
+            report = smoother.update(30, &config(100));
+        }
+        assert_eq!(report.cpm, 30 * 60);
+        assert_eq!(report.mode, Mode::Fast);
+
+        // A new bucket is used together with the previous one.
+        let report = smoother.update(0, &config(100));
+        assert_eq!(report.cpm, 150 * 60 / 6);
+        assert_eq!(report.mode, Mode::Fast);
+    }
+
+    #[test]
+    fn fast_window() {
+        let mut smoother = warmed_up(10);
+
+        // FAST mode uses the last 6 to 9 seconds while a bucket is being
+        // filled, and only the newest 5 seconds when the bucket is complete.
+        let cpm: Vec<u32> = (0..SHORT_PERIOD + 1)
+            .map(|_| smoother.update(0, &config(100)).cpm)
+            .collect();
+        assert_eq!(
+            cpm,
+            [50 * 60 / 6, 50 * 60 / 7, 50 * 60 / 8, 50 * 60 / 9, 0, 0]
+        );
+        assert_eq!(smoother.update(0, &config(100)).mode, Mode::Fast);
+    }
+
+    #[test]
+    fn slow_window() {
+        let mut smoother = warmed_up(10);
+
+        // SLOW mode uses the last 56 to 60 seconds, the oldest bucket is
+        // discarded when a new one is started.
+        let cpm: Vec<u32> = (0..SHORT_PERIOD + 1)
+            .map(|_| smoother.update(0, &config(1000)).cpm)
+            .collect();
+        assert_eq!(
+            cpm,
+            [
+                550 * 60 / 56,
+                550 * 60 / 57,
+                550 * 60 / 58,
+                550 * 60 / 59,
+                550,
+                500 * 60 / 56
+            ]
+        );
+        assert_eq!(smoother.update(0, &config(1000)).mode, Mode::Slow);
+    }
+
+    #[test]
+    fn fast_mode_warmup() {
+        let mut smoother = Smoother::new();
+
+        let report = smoother.update(30, &config(100));
+        assert_eq!(report.cpm, 30 * 60);
+        assert_eq!(report.mode, Mode::Warmup);
+
+        for _ in 1..SHORT_PERIOD {
+            smoother.update(30, &config(100));
+        }
+        let report = smoother.update(30, &config(100));
+        assert_eq!(report.cpm, 30 * 60);
+        assert_eq!(report.mode, Mode::Fast);
+    }
+
+    #[test]
+    fn high_count_rate() {
+        let mut smoother = warmed_up(0);
+
+        let mut report = smoother.update(260, &config(u16::MAX));
+        assert_eq!(report.cps, 260);
+        for _ in 1..SHORT_PERIOD {
+            report = smoother.update(0, &config(u16::MAX));
+        }
+        // 260 / (1 - 260 * 190e-6) = 273.5
+        assert_eq!(report.cpm, 273);
+        assert_eq!(report.mode, Mode::Slow);
+
+        // 1000 / (1 - 1000 * 190e-6) = 1234.6
+        for _ in 0..SHORT_PERIOD {
+            report = smoother.update(1000, &config(1000));
+        }
+        assert_eq!(report.corrected_cps, 1234);
+        assert_eq!(report.cpm, 1234 * 60);
+        assert_eq!(report.mode, Mode::Fast);
+
+        // Buckets saturate and the largest possible sum does not overflow.
+        let mut smoother = Smoother::new();
+        for _ in 0..LONG_PERIOD {
+            report = smoother.update(u16::MAX, &config(u16::MAX));
+        }
+        assert_eq!(report.cpm, u32::from(u16::MAX) * BUCKETS as u32);
+    }
+
+    #[test]
+    fn dead_time_correction() {
+        let mut smoother = warmed_up(0);
+
+        let report = smoother.update(10, &config(1000));
+        assert_eq!(report.cpm, 10 * 60 / 56);
+        assert!(!report.is_corrected());
+
+        // 200 / (1 - 200 * 190e-6) = 207.9
+        let report = smoother.update(200, &config(1000));
+        assert_eq!(report.cps, 200);
+        assert_eq!(report.corrected_cps, 207);
+        assert_eq!(report.cpm, (10 + 207) * 60 / 57);
+        assert!(report.is_corrected());
+        assert!(!report.saturated);
+    }
+
+    #[test]
+    fn saturation() {
+        let mut smoother = Smoother::new();
+
+        // Recommended maximum of SBM-20 is 1000 CPS.
+        assert!(!smoother.update(1000, &config(1000)).saturated);
+        assert!(smoother.update(1001, &config(1000)).saturated);
+    }
+
+    #[test]
+    fn dose() {
+        assert_eq!(dose_rate(0, 57), Fixed2::from_bits(0));
+        assert_eq!(dose_rate(1000, 57), Fixed2::from_bits(570));
+
+        let mut smoother = Smoother::new();
+        let config = Config {
+            tube: Tube::Lnd712,
+            ..Config::new()
+        };
+        let report = smoother.update(50, &config);
+        assert_eq!(report.dose, Fixed2::from_bits(50 * 60 * 93 / 100));
+    }
+}
@@ -1,8 +1,12 @@
 use crate::{clock::BoardClock, clock::Clock, hal};
 
+/// Duration of one timer tick in microseconds.
+pub const TICK_US: u16 = 32;
+
 /// A timer using `TC1` peripheral.
 ///
-/// Triggers `TIMER1_COMPA` interrupt.
+/// Triggers `TIMER1_COMPA` interrupt. Compare unit B can be used to schedule
+/// one-shot `TIMER1_COMPB` interrupts.
 pub struct Timer;
 
 impl Timer {
@@ -25,4 +29,38 @@ impl Timer {
         p.timsk.write(|w| w.ocie1a().set_bit());
         Self {}
     }
+
+    /// Schedule `TIMER1_COMPB` interrupt after `ticks` timer ticks.
+    ///
+    /// The first tick may be shorter than [`TICK_US`]. A previously scheduled
+    /// interrupt that did not happen yet is replaced.
+    ///
+    /// # Safety
+    /// This function should only be called with interrupts disabled after
+    /// the timer is initialized.
+    pub unsafe fn schedule_compare_b(ticks: u16) {
+        let p = unsafe { &*hal::pac::TC1::ptr() };
+
+        // The counter wraps to zero after reaching OCR1A.
+        let top = p.ocr1a.read().bits();
+        let mut at = p.tcnt1.read().bits() + ticks;
+        if at > top {
+            at -= top + 1;
+        }
+
+        p.ocr1b.write(|w| w.bits(at));
+        // Discard a pending compare match, the flag is cleared by writing one.
+        p.tifr.write(|w| w.ocf1b().set_bit());
+        p.timsk.modify(|_, w| w.ocie1b().set_bit());
+    }
+
+    /// Disable `TIMER1_COMPB` interrupt.
+    ///
+    /// # Safety
+    /// This function should only be called with interrupts disabled after
+    /// the timer is initialized.
+    pub unsafe fn cancel_compare_b() {
+        let p = unsafe { &*hal::pac::TC1::ptr() };
+        p.timsk.modify(|_, w| w.ocie1b().clear_bit());
+    }
 }
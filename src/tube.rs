@@ -0,0 +1,136 @@
+use progmem::{P, PStr, ProgMem};
+
+/// Supported GM tube types.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+#[repr(u8)]
+pub enum Tube {
+    Sbm20,
+    J305,
+    M4011,
+    Lnd712,
+}
+
+/// Parameters of a GM tube.
+#[derive(Clone, Copy)]
+pub struct TubeProfile {
+    /// Name of the tube.
+    pub name: PStr,
+    /// CPM to uSv/hr conversion factor.
+    ///
+    /// The factor is stored multiplied by 10,000, so the firmware does not need
+    /// floating point arithmetic. It is the bits of a `Fixed<4>` value.
+    pub scale_factor: u16,
+    /// Dead time in microseconds.
+    pub dead_time: u16,
+    /// Recommended maximum CPS.
+    ///
+    /// Dead time correction is not reliable above it, this is flagged in the report.
+    pub max_cps: u16,
+}
+
+/// Tube profiles indexed by `Tube`.
+///
+/// The values are approximate and based on manufacturer data.
+#[cfg_attr(target_arch = "avr", unsafe(link_section = ".progmem.data"))]
+static PROFILES: [ProgMem<TubeProfile>; Tube::ALL.len()] = unsafe {
+    [
+        ProgMem::new(TubeProfile {
+            name: P!("SBM-20"),
+            scale_factor: 57,
+            dead_time: 190,
+            max_cps: 1000,
+        }),
+        ProgMem::new(TubeProfile {
+            name: P!("J305"),
+            scale_factor: 81,
+            dead_time: 90,
+            max_cps: 2000,
+        }),
+        ProgMem::new(TubeProfile {
+            name: P!("M4011"),
+            scale_factor: 66,
+            dead_time: 80,
+            max_cps: 2500,
+        }),
+        ProgMem::new(TubeProfile {
+            name: P!("LND-712"),
+            scale_factor: 93,
+            dead_time: 90,
+            max_cps: 2200,
+        }),
+    ]
+};
+
+impl Tube {
+    /// All supported tubes.
+    pub const ALL: [Self; 4] = [Self::Sbm20, Self::J305, Self::M4011, Self::Lnd712];
+
+    /// Tube selected at build time using `tube-*` features, SBM-20 is used by default.
+    #[cfg(feature = "tube-j305")]
+    pub const DEFAULT: Self = Self::J305;
+    #[cfg(all(feature = "tube-m4011", not(feature = "tube-j305")))]
+    pub const DEFAULT: Self = Self::M4011;
+    #[cfg(all(
+        feature = "tube-lnd712",
+        not(any(feature = "tube-j305", feature = "tube-m4011"))
+    ))]
+    pub const DEFAULT: Self = Self::Lnd712;
+    #[cfg(not(any(feature = "tube-j305", feature = "tube-m4011", feature = "tube-lnd712")))]
+    pub const DEFAULT: Self = Self::Sbm20;
+
+    /// Returns the tube profile.
+    #[must_use]
+    pub fn profile(self) -> TubeProfile {
+        PROFILES[self as usize].load()
+    }
+
+    /// Convert from a numeric representation.
+    #[must_use]
+    pub fn from_u8(value: u8) -> Option<Self> {
+        Self::ALL.get(usize::from(value)).copied()
+    }
+
+    /// Find a tube by name ignoring ASCII case.
+    #[must_use]
+    pub fn from_name(name: &[u8]) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|tube| tube.profile().name.eq_ignore_ascii_case(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_name() {
+        assert_eq!(Tube::from_name(b"SBM-20"), Some(Tube::Sbm20));
+        assert_eq!(Tube::from_name(b"j305"), Some(Tube::J305));
+        assert_eq!(Tube::from_name(b"M4011"), Some(Tube::M4011));
+        assert_eq!(Tube::from_name(b"lnd-712"), Some(Tube::Lnd712));
+        assert_eq!(Tube::from_name(b"SBM20"), None);
+        assert_eq!(Tube::from_name(b""), None);
+    }
+
+    #[test]
+    fn from_u8() {
+        for tube in Tube::ALL {
+            assert_eq!(Tube::from_u8(tube as u8), Some(tube));
+        }
+        assert_eq!(Tube::from_u8(Tube::ALL.len() as u8), None);
+    }
+
+    #[test]
+    fn profile() {
+        let profile = Tube::Sbm20.profile();
+        assert!(profile.name.eq_ignore_ascii_case(b"SBM-20"));
+        assert_eq!(profile.scale_factor, 57);
+        assert_eq!(profile.dead_time, 190);
+        assert_eq!(profile.max_cps, 1000);
+
+        let profile = Tube::Lnd712.profile();
+        assert!(profile.name.eq_ignore_ascii_case(b"LND-712"));
+        assert_eq!(profile.scale_factor, 93);
+    }
+}
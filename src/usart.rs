@@ -4,6 +4,11 @@ use crate::clock::BoardClock;
 use crate::hal::port::{self, PD0, PD1};
 
 /// Wrapper around USART that can be used for output formatting.
+///
+/// Writing blocks until each byte is accepted by the USART. Longer output can be
+/// transmitted in background by `USART_UDRE` interrupt handler, which is enabled by
+/// [`Usart0::start_transmit`] and should call [`Usart0::transmit_next`]. Writing waits
+/// for the background transmission to finish, so interrupts have to be enabled then.
 pub struct Usart0 {
     p: attiny_hal::pac::USART,
 }
@@ -24,17 +29,57 @@ impl Usart0 {
         p.ubrrl.write(|w| w.bits((baudrate.ubrr & 0xFF) as u8));
         p.ucsra.write(|w| w.u2x().bit(baudrate.u2x));
 
-        // Enable receiver and transmitter.
-        p.ucsrb.write(|w| w.txen().set_bit().rxen().set_bit());
+        // Enable receiver, transmitter, and receive complete interrupt.
+        p.ucsrb
+            .write(|w| w.txen().set_bit().rxen().set_bit().rxcie().set_bit());
 
         Self { p }
     }
+
+    /// Read a received byte from the data register.
+    ///
+    /// # Safety
+    /// This function should only be called from `USART_RX` interrupt handler
+    /// after the USART is initialized.
+    #[must_use]
+    pub unsafe fn read_received() -> u8 {
+        let p = unsafe { &*attiny_hal::pac::USART::ptr() };
+        p.udr.read().bits()
+    }
+
+    /// Start background transmission by `USART_UDRE` interrupt handler.
+    pub fn start_transmit(&mut self) {
+        self.p.ucsrb.modify(|_, w| w.udrie().set_bit());
+    }
+
+    /// Returns `true` if background transmission is in progress.
+    #[must_use]
+    pub fn is_transmitting(&self) -> bool {
+        self.p.ucsrb.read().udrie().bit_is_set()
+    }
+
+    /// Transmit the next byte of background output.
+    ///
+    /// `None` stops the transmission and disables data register empty interrupt
+    /// until [`Usart0::start_transmit`] is called again.
+    ///
+    /// # Safety
+    /// This function should only be called from `USART_UDRE` interrupt handler
+    /// after the USART is initialized.
+    pub unsafe fn transmit_next(b: Option<u8>) {
+        let p = unsafe { &*attiny_hal::pac::USART::ptr() };
+
+        match b {
+            Some(b) => p.udr.write(|w| w.bits(b)),
+            None => p.ucsrb.modify(|_, w| w.udrie().clear_bit()),
+        }
+    }
 }
 
 impl NanoWrite for Usart0 {
     fn write_byte(&mut self, b: u8) {
+        while self.is_transmitting() {}
         while self.p.ucsra.read().udre().bit_is_clear() {}
-
         self.p.udr.write(|w| w.bits(b));
     }
 }
@@ -0,0 +1,29 @@
+use avr_device::interrupt;
+
+use crate::hal::pac::WDT;
+
+/// Watchdog Interrupt Enable bit in `WDTCR`.
+const WDIE: u8 = 1 << 6;
+/// Watchdog Change Enable bit in `WDTCR`.
+const WDCE: u8 = 1 << 4;
+/// Watchdog System Reset Enable bit in `WDTCR`.
+const WDE: u8 = 1 << 3;
+
+/// Watchdog timer used as a periodic interrupt source.
+///
+/// Triggers `WDT_OVERFLOW` interrupt about every 16 ms.
+pub struct Watchdog;
+
+impl Watchdog {
+    /// Start the watchdog timer in interrupt mode.
+    pub fn new(p: WDT) -> Self {
+        // Prescaler is set to zero (2K cycles of the 128 kHz oscillator, 16 ms).
+        // Changing the prescaler requires a timed sequence.
+        interrupt::free(|_| {
+            p.wdtcr.write(|w| unsafe { w.bits(WDCE | WDE) });
+            p.wdtcr.write(|w| unsafe { w.bits(WDIE) });
+        });
+
+        Self {}
+    }
+}